@@ -0,0 +1,1313 @@
+/// Applying the editor's change lists to the YAML sources
+///
+
+use crate::wordnet::{Lexicon, Synset, Synsets, Entries, Entry, Sense, SynsetId, SenseId,
+    MemberSynset, PartOfSpeech, PosKey, Example, ILIID, WordNetYAMLIOError, entry_key};
+use thiserror::Error;
+use serde::{Serialize, Deserialize};
+use serde_yaml::{Mapping, Value};
+use std::collections::{HashMap, HashSet, BTreeMap};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// A single change as produced by `editor.vue`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    ChangeDefinition { synset : String, definition : String },
+    ChangeIli { synset : String, #[serde(default)] ili : Option<String> },
+    ChangeWikidata { synset : String, #[serde(default)] wikidata : Option<String> },
+    ChangeSource { synset : String, #[serde(default)] source : Option<String> },
+    ChangeMembers { synset : String, members : Vec<String> },
+    UpdateRelations { synset : String, relations : Vec<RelationChange> },
+    AddExample { synset : String, example : String },
+    DeleteExample { synset : String, number : usize },
+    DeleteSynset { synset : String, #[serde(default)] reason : String,
+        #[serde(default)] superseded_by : Option<String> },
+    AddSynset { definition : String, lexfile : String, lemmas : Vec<String> },
+}
+
+/// A relation of a synset as listed in an `update_relations` change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelationChange {
+    pub target : SynsetId,
+    pub relation : String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_lemma : Option<String>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_lemma : Option<String>,
+}
+
+/// Synset relations that are only stored on their target in the YAML files
+const SYNSET_INVERSES : &[(&str, &str)] = &[
+    ("hyponym", "hypernym"),
+    ("instance_hyponym", "instance_hypernym"),
+    ("holo_member", "mero_member"),
+    ("holo_part", "mero_part"),
+    ("holo_substance", "mero_substance"),
+    ("is_caused_by", "causes"),
+    ("is_exemplified_by", "exemplifies"),
+    ("is_entailed_by", "entails"),
+    ("has_domain_region", "domain_region"),
+];
+
+/// Sense relations that are only stored on their target in the YAML files
+const SENSE_INVERSES : &[(&str, &str)] = &[
+    ("is_participle_of", "participle"),
+    ("is_pertainym_of", "pertainym"),
+    ("is_exemplified_by_sense", "exemplifies_sense"),
+    ("is_agent_of", "agent"),
+    ("is_material_of", "material"),
+    ("is_event_of", "event"),
+    ("is_instrument_of", "instrument"),
+    ("is_location_of", "location"),
+    ("is_by_means_of", "by_means_of"),
+    ("is_undergoer_of", "undergoer"),
+    ("is_property_of", "property"),
+    ("is_result_of", "result"),
+    ("is_state_of", "state"),
+    ("is_used_by", "uses"),
+    ("is_destination_of", "destination"),
+    ("is_body_part_of", "body_part"),
+    ("is_vehicle_of", "vehicle"),
+];
+
+/// Relations that are recorded in both directions
+const SYMMETRIC : &[&str] = &["also", "similar", "antonym", "derivation"];
+
+/// The lexicographer files in the order of their numbers in sense keys
+const LEXFILES : &[&str] = &[
+    "adj.all", "adj.pert", "adv.all", "noun.Tops", "noun.act", "noun.animal",
+    "noun.artifact", "noun.attribute", "noun.body", "noun.cognition",
+    "noun.communication", "noun.event", "noun.feeling", "noun.food", "noun.group",
+    "noun.location", "noun.motive", "noun.object", "noun.person", "noun.phenomenon",
+    "noun.plant", "noun.possession", "noun.process", "noun.quantity", "noun.relation",
+    "noun.shape", "noun.state", "noun.substance", "noun.time", "verb.body",
+    "verb.change", "verb.cognition", "verb.communication", "verb.competition",
+    "verb.consumption", "verb.contact", "verb.creation", "verb.emotion", "verb.motion",
+    "verb.perception", "verb.possession", "verb.social", "verb.stative", "verb.weather",
+    "adj.ppl"];
+
+/// What earlier change lists did to the sources, which the loaded lexicon
+/// does not show until the server is restarted
+#[derive(Default, Clone)]
+pub struct Edits {
+    next_id : Option<u64>,
+    created : HashMap<SynsetId, String>,
+    deleted : HashSet<SynsetId>,
+    /// The current relations of the synsets whose relations were changed
+    relations : HashMap<SynsetId, Vec<RelationChange>>,
+}
+
+/// Validate and apply a list of changes to the YAML files in `folder`.
+/// Nothing is written unless every change is valid, and `edits` is only
+/// updated once every file is written. Returns the names of the files that
+/// were rewritten.
+pub fn apply_changes<P: AsRef<Path>>(folder : P, lexicon : &Lexicon, edits : &mut Edits, changes : &[Change])
+    -> Result<Vec<String>, ChangeError> {
+    let mut workspace = Workspace::new(folder.as_ref(), lexicon, edits.clone());
+    for change in changes {
+        workspace.apply(change)?;
+    }
+    let (written, new_edits) = workspace.save()?;
+    *edits = new_edits;
+    Ok(written)
+}
+
+/// The YAML files touched by a list of changes
+struct Workspace<'a> {
+    folder : PathBuf,
+    lexicon : &'a Lexicon,
+    synsets : HashMap<String, Synsets>,
+    entries : HashMap<String, Entries>,
+    /// The text of each file as it was read
+    originals : HashMap<String, String>,
+    edits : Edits,
+    deprecations : Vec<Deprecation>,
+    last : Option<SynsetId>,
+}
+
+/// A line of `deprecations.csv`, which records deleted synsets
+struct Deprecation {
+    id : SynsetId,
+    ili : Option<ILIID>,
+    superseded_by : Option<SynsetId>,
+    superseding_ili : Option<ILIID>,
+    reason : String,
+}
+
+impl Deprecation {
+    fn to_csv(&self) -> String {
+        let fields = [
+            format!("oewn-{}", self.id.as_str()),
+            self.ili.as_ref().map(|i| i.as_str().to_string()).unwrap_or_default(),
+            self.superseded_by.as_ref().map(|s| format!("oewn-{}", s.as_str())).unwrap_or_default(),
+            self.superseding_ili.as_ref().map(|i| i.as_str().to_string()).unwrap_or_default(),
+            self.reason.clone()
+        ];
+        let fields : Vec<String> = fields.iter()
+            .map(|f| format!("\"{}\"", f.replace('"', "\"\"")))
+            .collect();
+        format!("{}\n", fields.join(","))
+    }
+}
+
+impl<'a> Workspace<'a> {
+    fn new(folder : &Path, lexicon : &'a Lexicon, edits : Edits) -> Workspace<'a> {
+        Workspace {
+            folder: folder.to_path_buf(),
+            lexicon,
+            synsets: HashMap::new(),
+            entries: HashMap::new(),
+            originals: HashMap::new(),
+            edits,
+            deprecations: Vec::new(),
+            last: None
+        }
+    }
+
+    fn apply(&mut self, change : &Change) -> Result<(), ChangeError> {
+        match change {
+            Change::ChangeDefinition { synset, definition } => {
+                if definition.trim().is_empty() {
+                    return Err(ChangeError::Invalid(format!("Empty definition for {}", synset)));
+                }
+                let ssid = self.resolve(synset)?;
+                let synset = self.synset_mut(&ssid)?;
+                if synset.definition.is_empty() {
+                    synset.definition.push(definition.clone());
+                } else {
+                    synset.definition[0] = definition.clone();
+                }
+            },
+            Change::ChangeIli { synset, ili } => {
+                let ssid = self.resolve(synset)?;
+                let ili = match ili.as_ref().map(|x| x.trim()) {
+                    None | Some("") => None,
+                    Some(ili) => Some(ILIID::parse(ili).map_err(ChangeError::Invalid)?)
+                };
+                self.synset_mut(&ssid)?.ili = ili;
+            },
+            Change::ChangeWikidata { synset, wikidata } => {
+                let ssid = self.resolve(synset)?;
+                self.synset_mut(&ssid)?.wikidata = non_empty(wikidata);
+            },
+            Change::ChangeSource { synset, source } => {
+                let ssid = self.resolve(synset)?;
+                self.synset_mut(&ssid)?.source = non_empty(source);
+            },
+            Change::ChangeMembers { synset, members } => {
+                let ssid = self.resolve(synset)?;
+                if members.is_empty() || members.iter().any(|m| m.trim().is_empty()) {
+                    return Err(ChangeError::Invalid(format!("Bad member list for {}", ssid.as_str())));
+                }
+                let lexname = self.lexname(&ssid)?;
+                let (old, pos) = {
+                    let synset = self.synset_mut(&ssid)?;
+                    (synset.members.clone(), synset.part_of_speech.clone())
+                };
+                let mut removed = HashSet::new();
+                for lemma in old.iter().filter(|l| !members.contains(*l)) {
+                    removed.extend(self.remove_sense(lemma, &ssid)?);
+                }
+                self.remove_sense_targets(&removed)?;
+                for lemma in members.iter().filter(|l| !old.contains(*l)) {
+                    self.add_sense(lemma, &ssid, &pos, &lexname)?;
+                }
+                self.synset_mut(&ssid)?.members = members.clone();
+            },
+            Change::UpdateRelations { synset, relations } => {
+                let ssid = self.resolve(synset)?;
+                if let Some(r) = relations.iter().find(|r| [&r.source_lemma, &r.target_lemma].iter()
+                    .any(|l| l.as_ref().is_some_and(|l| l.trim().is_empty()))) {
+                    return Err(ChangeError::Invalid(format!("Empty lemma in {} relation of {}", r.relation, ssid.as_str())));
+                }
+                self.lexname(&ssid)?;
+                let old = self.relations_of(&ssid);
+                for r in old.iter().filter(|r| !relations.contains(*r)) {
+                    self.edit_relation(&ssid, r, false)?;
+                }
+                for r in relations.iter().filter(|r| !old.contains(*r)) {
+                    self.edit_relation(&ssid, r, true)?;
+                }
+            },
+            Change::AddExample { synset, example } => {
+                if example.trim().is_empty() {
+                    return Err(ChangeError::Invalid(format!("Empty example for {}", synset)));
+                }
+                let ssid = self.resolve(synset)?;
+                self.synset_mut(&ssid)?.example.push(Example { text: example.clone(), source: None });
+            },
+            Change::DeleteExample { synset, number } => {
+                let ssid = self.resolve(synset)?;
+                let synset = self.synset_mut(&ssid)?;
+                if *number >= synset.example.len() {
+                    return Err(ChangeError::Invalid(format!("No example {} for {}", number, ssid.as_str())));
+                }
+                synset.example.remove(*number);
+            },
+            Change::DeleteSynset { synset, reason, superseded_by } => {
+                let ssid = self.resolve(synset)?;
+                let superseded_by = match superseded_by.as_ref().map(|x| x.trim()).filter(|x| !x.is_empty()) {
+                    Some(by) => {
+                        let by = self.resolve(by)?;
+                        if by == ssid {
+                            return Err(ChangeError::Invalid(format!("{} cannot supersede itself", ssid.as_str())));
+                        }
+                        Some(by)
+                    },
+                    None => None
+                };
+                let superseding_ili = match superseded_by.as_ref() {
+                    Some(by) => self.synset_mut(by)?.ili.clone(),
+                    None => None
+                };
+                let lexname = self.lexname(&ssid)?;
+                for r in self.relations_of(&ssid) {
+                    self.edit_relation(&ssid, &r, false)?;
+                }
+                let removed = self.synsets_mut(&lexname)?.0.remove(&ssid);
+                let mut ili = None;
+                if let Some(removed) = removed {
+                    let mut senses = HashSet::new();
+                    for lemma in removed.members.iter() {
+                        senses.extend(self.remove_sense(lemma, &ssid)?);
+                    }
+                    self.remove_sense_targets(&senses)?;
+                    ili = removed.ili;
+                }
+                // A synset that was never published needs no deprecation
+                if self.edits.created.remove(&ssid).is_none() {
+                    self.deprecations.push(Deprecation {
+                        id: ssid.clone(),
+                        ili,
+                        superseded_by,
+                        superseding_ili,
+                        reason: reason.trim().to_string()
+                    });
+                }
+                self.edits.deleted.insert(ssid);
+            },
+            Change::AddSynset { definition, lexfile, lemmas } => {
+                if definition.trim().is_empty() || lemmas.is_empty()
+                    || lemmas.iter().any(|l| l.trim().is_empty()) {
+                    return Err(ChangeError::Invalid("A new synset needs a definition and lemmas".to_string()));
+                }
+                let pos = lexfile_pos(lexfile)
+                    .ok_or_else(|| ChangeError::Invalid(format!("Unknown lexicographer file {}", lexfile)))?;
+                let ssid = self.new_synset_id(&pos);
+                if self.lexicon.synset_by_id(&ssid).is_some() || self.synsets_mut(lexfile)?.0.contains_key(&ssid) {
+                    return Err(ChangeError::Invalid(format!("Synset {} already exists", ssid.as_str())));
+                }
+                let mut synset : Synset = serde_json::from_value(serde_json::json!({
+                    "definition": [definition],
+                    "members": lemmas,
+                    "partOfSpeech": pos.str()
+                })).map_err(|e| ChangeError::Invalid(format!("Could not create synset: {}", e)))?;
+                synset.id = Some(ssid.clone());
+                synset.lexname = Some(lexfile.clone());
+                self.synsets_mut(lexfile)?.0.insert(ssid.clone(), synset);
+                self.edits.created.insert(ssid.clone(), lexfile.clone());
+                self.last = Some(ssid.clone());
+                for lemma in lemmas.iter() {
+                    self.add_sense(lemma, &ssid, &pos, lexfile)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write every file that was changed back to disk, with the deleted
+    /// synsets added to the `deprecations.csv` next to the YAML folder
+    fn save(self) -> Result<(Vec<String>, Edits), ChangeError> {
+        let mut files = Vec::new();
+        for (lexname, synsets) in self.synsets.iter() {
+            let file_name = format!("{}.yaml", lexname);
+            let original = &self.originals[&file_name];
+            let content = to_yaml(&file_name, original, synsets)?;
+            if content != original.as_bytes() {
+                files.push((self.folder.join(&file_name), file_name, content));
+            }
+        }
+        for (key, entries) in self.entries.iter() {
+            let file_name = format!("entries-{}.yaml", key);
+            let original = &self.originals[&file_name];
+            let content = to_yaml(&file_name, original, entries)?;
+            if content != original.as_bytes() {
+                files.push((self.folder.join(&file_name), file_name, content));
+            }
+        }
+        if !self.deprecations.is_empty() {
+            let file_name = "deprecations.csv".to_string();
+            let path = self.folder.parent().unwrap_or(self.folder.as_path()).join(&file_name);
+            let mut content = match fs::read(&path) {
+                Ok(content) => content,
+                Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+                Err(e) => return Err(WordNetYAMLIOError::Io(format!("Error reading {} due to {}", file_name, e)).into())
+            };
+            for deprecation in self.deprecations.iter() {
+                content.extend(deprecation.to_csv().into_bytes());
+            }
+            files.push((path, file_name, content));
+        }
+        write_files(&files)?;
+        let mut written : Vec<String> = files.into_iter().map(|(_, file_name, _)| file_name).collect();
+        written.sort();
+        Ok((written, self.edits))
+    }
+
+    /// Resolve a synset ID from the editor, where `last` is the most recently
+    /// added synset
+    fn resolve(&self, id : &str) -> Result<SynsetId, ChangeError> {
+        if id == "last" {
+            self.last.clone().ok_or_else(|| ChangeError::Invalid("No synset has been added".to_string()))
+        } else if id.starts_with("oewn-") {
+            Ok(SynsetId::new(&id[5..]))
+        } else {
+            Ok(SynsetId::new(id))
+        }
+    }
+
+    /// The lexicographer file that a synset is stored in
+    fn lexname(&self, ssid : &SynsetId) -> Result<String, ChangeError> {
+        if let Some(lexname) = self.edits.created.get(ssid) {
+            Ok(lexname.clone())
+        } else if self.edits.deleted.contains(ssid) {
+            Err(ChangeError::Invalid(format!("Synset {} has been deleted", ssid.as_str())))
+        } else {
+            self.lexicon.synset_by_id(ssid).map(|s| s.lexname)
+                .ok_or_else(|| ChangeError::Invalid(format!("Synset {} does not exist", ssid.as_str())))
+        }
+    }
+
+    fn synsets_mut(&mut self, lexname : &str) -> Result<&mut Synsets, ChangeError> {
+        if !self.synsets.contains_key(lexname) {
+            let file_name = format!("{}.yaml", lexname);
+            let (synsets, original) = read_yaml(&self.folder.join(&file_name), &file_name)?;
+            self.synsets.insert(lexname.to_string(), synsets);
+            self.originals.insert(file_name, original);
+        }
+        Ok(self.synsets.get_mut(lexname).unwrap())
+    }
+
+    fn synset_mut(&mut self, ssid : &SynsetId) -> Result<&mut Synset, ChangeError> {
+        let lexname = self.lexname(ssid)?;
+        self.synsets_mut(&lexname)?.0.get_mut(ssid)
+            .ok_or_else(|| ChangeError::Invalid(format!("Synset {} is not in {}.yaml", ssid.as_str(), lexname)))
+    }
+
+    fn entries_mut(&mut self, lemma : &str) -> Result<&mut Entries, ChangeError> {
+        self.entries_file_mut(&entry_key(lemma))
+    }
+
+    /// The entries of `entries-<key>.yaml`
+    fn entries_file_mut(&mut self, key : &str) -> Result<&mut Entries, ChangeError> {
+        if !self.entries.contains_key(key) {
+            let file_name = format!("entries-{}.yaml", key);
+            let (entries, original) = read_yaml(&self.folder.join(&file_name), &file_name)?;
+            self.entries.insert(key.to_string(), entries);
+            self.originals.insert(file_name, original);
+        }
+        Ok(self.entries.get_mut(key).unwrap())
+    }
+
+    fn sense_mut(&mut self, lemma : &str, ssid : &SynsetId) -> Result<&mut Sense, ChangeError> {
+        self.entries_mut(lemma)?.0.get_mut(lemma).into_iter()
+            .flat_map(|by_pos| by_pos.values_mut())
+            .flat_map(|entry| entry.sense.iter_mut())
+            .find(|sense| &sense.synset == ssid)
+            .ok_or_else(|| ChangeError::Invalid(format!("{} is not a member of {}", lemma, ssid.as_str())))
+    }
+
+    /// Add a new sense of `lemma` for a synset to the entries
+    fn add_sense(&mut self, lemma : &str, ssid : &SynsetId, pos : &PartOfSpeech, lexname : &str) -> Result<(), ChangeError> {
+        let id = self.new_sense_id(lemma, ssid, pos, lexname)?;
+        let sense : Sense = serde_json::from_value(serde_json::json!({
+            "id": id.as_str(),
+            "synset": ssid.as_str()
+        })).map_err(|e| ChangeError::Invalid(format!("Could not create sense: {}", e)))?;
+        let by_pos = self.entries_mut(lemma)?.0.entry(lemma.to_string())
+            .or_insert_with(BTreeMap::new);
+        let poskey = by_pos.keys()
+            .find(|k| k.as_str() == pos.str() || k.as_str().starts_with(&format!("{}-", pos.str())))
+            .cloned()
+            .unwrap_or_else(|| PosKey::new(pos.str()));
+        by_pos.entry(poskey).or_insert_with(Entry::default).sense.push(sense);
+        Ok(())
+    }
+
+    /// Remove the sense of `lemma` for a synset, dropping empty entries.
+    /// Returns the IDs of the removed senses
+    fn remove_sense(&mut self, lemma : &str, ssid : &SynsetId) -> Result<Vec<SenseId>, ChangeError> {
+        let entries = self.entries_mut(lemma)?;
+        let mut removed = Vec::new();
+        let empty = if let Some(by_pos) = entries.0.get_mut(lemma) {
+            for entry in by_pos.values_mut() {
+                removed.extend(entry.sense.iter().filter(|sense| &sense.synset == ssid).map(|sense| sense.id.clone()));
+                entry.sense.retain(|sense| &sense.synset != ssid);
+            }
+            by_pos.retain(|_, entry| !entry.sense.is_empty());
+            by_pos.is_empty()
+        } else {
+            false
+        };
+        if empty {
+            entries.0.remove(lemma);
+        }
+        Ok(removed)
+    }
+
+    /// Remove the relations of every entry that point to removed senses,
+    /// including those, such as `also` and `similar`, that the lexicon does
+    /// not show on the synsets and so are not undone as relations
+    fn remove_sense_targets(&mut self, removed : &HashSet<SenseId>) -> Result<(), ChangeError> {
+        if removed.is_empty() {
+            return Ok(());
+        }
+        let mut keys = Vec::new();
+        for file in fs::read_dir(&self.folder)
+            .map_err(|e| WordNetYAMLIOError::Io(format!("Could not list directory: {}", e)))? {
+            let file = file.map_err(|e| WordNetYAMLIOError::Io(format!("Could not list directory: {}", e)))?;
+            let file_name = file.file_name().to_string_lossy().to_string();
+            if let Some(key) = file_name.strip_prefix("entries-").and_then(|x| x.strip_suffix(".yaml")) {
+                keys.push(key.to_string());
+            }
+        }
+        for key in keys {
+            let senses = self.entries_file_mut(&key)?.0.values_mut()
+                .flat_map(|by_pos| by_pos.values_mut())
+                .flat_map(|entry| entry.sense.iter_mut());
+            for sense in senses {
+                for targets in sense_targets(sense) {
+                    targets.retain(|t| !removed.contains(t));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Create a sense key for a new sense, e.g., `dog%1:05:00::`
+    fn new_sense_id(&mut self, lemma : &str, ssid : &SynsetId, pos : &PartOfSpeech, lexname : &str) -> Result<SenseId, ChangeError> {
+        let lexfile = LEXFILES.iter().position(|x| *x == lexname)
+            .ok_or_else(|| ChangeError::Invalid(format!("Unknown lexicographer file {}", lexname)))?;
+        let ss_type = ss_type(pos);
+        let key_lemma = sense_key_lemma(lemma);
+        let mut used = HashSet::new();
+        for (l, by_pos) in self.entries_mut(lemma)?.0.iter() {
+            if sense_key_lemma(l) != key_lemma {
+                continue;
+            }
+            for sense in by_pos.values().flat_map(|e| e.sense.iter()) {
+                if let Some((_, t, f, lex_id)) = parse_sense_key(sense.id.as_str()) {
+                    // Heads and satellites share lexical IDs
+                    if f == lexfile as u32 && (t == ss_type || (is_adjective(t) && is_adjective(ss_type))) {
+                        used.insert(lex_id);
+                    }
+                }
+            }
+        }
+        let lex_id = (0..).find(|i| !used.contains(i)).unwrap();
+        let head = if *pos == PartOfSpeech::s {
+            let similar = self.synset_mut(ssid)?.similar.first().cloned()
+                .ok_or_else(|| ChangeError::Invalid(format!("Satellite {} has no head synset", ssid.as_str())))?;
+            let head_lemma = self.synset_mut(&similar)?.members.first().cloned()
+                .ok_or_else(|| ChangeError::Invalid(format!("Head synset {} has no members", similar.as_str())))?;
+            let head_key = self.sense_mut(&head_lemma, &similar)?.id.clone();
+            let head_id = parse_sense_key(head_key.as_str()).map(|x| x.3).unwrap_or(0);
+            format!("{}:{:02}", sense_key_lemma(&head_lemma), head_id)
+        } else {
+            ":".to_string()
+        };
+        Ok(SenseId::new(&format!("{}%{}:{:02}:{:02}:{}", key_lemma, ss_type, lexfile, lex_id, head)))
+    }
+
+    /// Choose an unused synset identifier
+    fn new_synset_id(&mut self, pos : &PartOfSpeech) -> SynsetId {
+        let next = match self.edits.next_id {
+            Some(n) => n,
+            None => self.lexicon.synset_ids.iter()
+                .filter_map(|id| id.as_str().split('-').next().and_then(|x| x.parse::<u64>().ok()))
+                .max().unwrap_or(0) + 1
+        };
+        self.edits.next_id = Some(next + 1);
+        SynsetId::new(&format!("{:08}-{}", next, pos.str()))
+    }
+
+    /// Add or remove a relation from `source`, storing it in the direction the
+    /// YAML files use
+    fn edit_relation(&mut self, source : &SynsetId, r : &RelationChange, add : bool) -> Result<(), ChangeError> {
+        self.lexname(&r.target)?;
+        let is_sense = is_sense_relation(&r.relation);
+        let (rel, flipped) = canonical_relation(&r.relation, is_sense)
+            .ok_or_else(|| ChangeError::Invalid(format!("Unknown relation {}", r.relation)))?;
+        let (from, to) = if flipped { (&r.target, source) } else { (source, &r.target) };
+        if is_sense {
+            let source_lemma = r.source_lemma.as_ref().filter(|l| !l.trim().is_empty())
+                .ok_or_else(|| ChangeError::Invalid(format!("No source lemma for {}", r.relation)))?;
+            let target_lemma = r.target_lemma.as_ref().filter(|l| !l.trim().is_empty())
+                .ok_or_else(|| ChangeError::Invalid(format!("No target lemma for {}", r.relation)))?;
+            let (from_lemma, to_lemma) = if flipped { (target_lemma, source_lemma) } else { (source_lemma, target_lemma) };
+            self.edit_sense_relation(rel, from_lemma, from, to_lemma, to, add)?;
+            if SYMMETRIC.contains(&rel) {
+                self.edit_sense_relation(rel, to_lemma, to, from_lemma, from, add)?;
+            }
+        } else {
+            self.edit_synset_relation(rel, from, to, add)?;
+            if SYMMETRIC.contains(&rel) {
+                self.edit_synset_relation(rel, to, from, add)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The relations of a synset as the editor sends them, including the
+    /// changes made by earlier change lists
+    fn relations_of(&self, ssid : &SynsetId) -> Vec<RelationChange> {
+        match self.edits.relations.get(ssid) {
+            Some(relations) => relations.clone(),
+            None => self.lexicon.synset_by_id(ssid)
+                .map(|s| member_relations(&s))
+                .unwrap_or_default()
+        }
+    }
+
+    /// Record a stored relation, and the inverse that the lexicon derives
+    /// for its target, in the relations of the edited synsets
+    fn record_relation(&mut self, from : &SynsetId, r : RelationChange, is_sense : bool, add : bool) {
+        let inverse = derived_inverse(&r.relation, is_sense).map(|inv| RelationChange {
+            target: from.clone(),
+            relation: inv.to_string(),
+            source_lemma: r.target_lemma.clone(),
+            target_lemma: r.source_lemma.clone()
+        });
+        let target = r.target.clone();
+        self.record(from, r, add);
+        if let Some(inverse) = inverse {
+            self.record(&target, inverse, add);
+        }
+    }
+
+    fn record(&mut self, ssid : &SynsetId, r : RelationChange, add : bool) {
+        let mut relations = self.relations_of(ssid);
+        if !add {
+            relations.retain(|x| x != &r);
+        } else if !relations.contains(&r) {
+            relations.push(r);
+        }
+        self.edits.relations.insert(ssid.clone(), relations);
+    }
+
+    fn edit_synset_relation(&mut self, rel : &str, from : &SynsetId, to : &SynsetId, add : bool) -> Result<(), ChangeError> {
+        let synset = self.synset_mut(from)?;
+        let targets = synset_relation(synset, rel)
+            .ok_or_else(|| ChangeError::Invalid(format!("Unknown relation {}", rel)))?;
+        if !add {
+            targets.retain(|t| t != to);
+        } else if !targets.contains(to) {
+            targets.push(to.clone());
+        }
+        self.record_relation(from, RelationChange {
+            target: to.clone(),
+            relation: rel.to_string(),
+            source_lemma: None,
+            target_lemma: None
+        }, false, add);
+        Ok(())
+    }
+
+    fn edit_sense_relation(&mut self, rel : &str, from_lemma : &str, from : &SynsetId,
+        to_lemma : &str, to : &SynsetId, add : bool) -> Result<(), ChangeError> {
+        let target = self.sense_mut(to_lemma, to)?.id.clone();
+        let sense = self.sense_mut(from_lemma, from)?;
+        let targets = sense_relation(sense, rel)
+            .ok_or_else(|| ChangeError::Invalid(format!("Unknown relation {}", rel)))?;
+        if !add {
+            targets.retain(|t| t != &target);
+        } else if !targets.contains(&target) {
+            targets.push(target);
+        }
+        self.record_relation(from, RelationChange {
+            target: to.clone(),
+            relation: rel.to_string(),
+            source_lemma: Some(from_lemma.to_string()),
+            target_lemma: Some(to_lemma.to_string())
+        }, true, add);
+        Ok(())
+    }
+}
+
+/// List all relations of a synset as the editor sends them
+pub fn member_relations(synset : &MemberSynset) -> Vec<RelationChange> {
+    let mut relations = Vec::new();
+    macro_rules! add_rel {
+        ($rel:ident) => {
+            for r in synset.$rel.iter() {
+                relations.push(RelationChange {
+                    target: r.clone(),
+                    relation: stringify!($rel).to_string(),
+                    source_lemma: None,
+                    target_lemma: None
+                });
+            }
+        }
+    }
+    add_rel!(also);
+    add_rel!(attribute);
+    add_rel!(causes);
+    add_rel!(domain_region);
+    add_rel!(domain_topic);
+    add_rel!(exemplifies);
+    add_rel!(entails);
+    add_rel!(hypernym);
+    add_rel!(instance_hypernym);
+    add_rel!(mero_member);
+    add_rel!(mero_part);
+    add_rel!(mero_substance);
+    add_rel!(similar);
+    add_rel!(hyponym);
+    add_rel!(is_caused_by);
+    add_rel!(has_domain_region);
+    add_rel!(has_domain_topic);
+    add_rel!(is_exemplified_by);
+    add_rel!(is_entailed_by);
+    add_rel!(instance_hyponym);
+    add_rel!(holo_member);
+    add_rel!(holo_part);
+    add_rel!(holo_substance);
+    macro_rules! add_srel {
+        ($rel:ident) => {
+            for r in synset.$rel.iter() {
+                relations.push(RelationChange {
+                    target: r.target_synset.clone(),
+                    relation: stringify!($rel).to_string(),
+                    source_lemma: Some(r.source_lemma.clone()),
+                    target_lemma: Some(r.target_lemma.clone())
+                });
+            }
+        }
+    }
+    add_srel!(antonym);
+    add_srel!(participle);
+    add_srel!(is_participle_of);
+    add_srel!(pertainym);
+    add_srel!(is_pertainym_of);
+    add_srel!(derivation);
+    add_srel!(exemplifies_sense);
+    add_srel!(is_exemplified_by_sense);
+    add_srel!(agent);
+    add_srel!(is_agent_of);
+    add_srel!(material);
+    add_srel!(is_material_of);
+    add_srel!(event);
+    add_srel!(is_event_of);
+    add_srel!(instrument);
+    add_srel!(is_instrument_of);
+    add_srel!(location);
+    add_srel!(is_location_of);
+    add_srel!(by_means_of);
+    add_srel!(is_by_means_of);
+    add_srel!(undergoer);
+    add_srel!(is_undergoer_of);
+    add_srel!(property);
+    add_srel!(is_property_of);
+    add_srel!(result);
+    add_srel!(is_result_of);
+    add_srel!(state);
+    add_srel!(is_state_of);
+    add_srel!(uses);
+    add_srel!(is_used_by);
+    add_srel!(destination);
+    add_srel!(is_destination_of);
+    add_srel!(body_part);
+    add_srel!(is_body_part_of);
+    add_srel!(vehicle);
+    add_srel!(is_vehicle_of);
+    relations
+}
+
+fn is_sense_relation(rel : &str) -> bool {
+    SENSE_INVERSES.iter().any(|(inv, r)| *inv == rel || *r == rel)
+        || rel == "antonym" || rel == "derivation"
+}
+
+/// The stored name of a relation and whether it is stored on the target
+fn canonical_relation(rel : &str, is_sense : bool) -> Option<(&'static str, bool)> {
+    let inverses = if is_sense { SENSE_INVERSES } else { SYNSET_INVERSES };
+    for (inv, r) in inverses.iter() {
+        if *inv == rel {
+            return Some((r, true));
+        } else if *r == rel {
+            return Some((r, false));
+        }
+    }
+    [ "also", "attribute", "causes", "domain_region", "domain_topic", "exemplifies",
+      "entails", "hypernym", "instance_hypernym", "mero_member", "mero_part",
+      "mero_substance", "similar", "has_domain_topic", "antonym", "derivation" ]
+        .iter().find(|r| **r == rel).map(|r| (*r, false))
+}
+
+/// The relation that the lexicon derives on the target of a stored relation
+fn derived_inverse(rel : &str, is_sense : bool) -> Option<&'static str> {
+    if is_sense {
+        match rel {
+            "antonym" => Some("antonym"),
+            "derivation" => Some("derivation"),
+            _ => SENSE_INVERSES.iter().find(|(_, r)| *r == rel).map(|(inv, _)| *inv)
+        }
+    } else {
+        // `has_domain_region` is stored, as `domain_region` on the target,
+        // but never derived
+        SYNSET_INVERSES.iter().find(|(inv, r)| *r == rel && *inv != "has_domain_region").map(|(inv, _)| *inv)
+    }
+}
+
+fn synset_relation<'b>(synset : &'b mut Synset, rel : &str) -> Option<&'b mut Vec<SynsetId>> {
+    match rel {
+        "also" => Some(&mut synset.also),
+        "attribute" => Some(&mut synset.attribute),
+        "causes" => Some(&mut synset.causes),
+        "domain_region" => Some(&mut synset.domain_region),
+        "domain_topic" => Some(&mut synset.domain_topic),
+        "exemplifies" => Some(&mut synset.exemplifies),
+        "entails" => Some(&mut synset.entails),
+        "hypernym" => Some(&mut synset.hypernym),
+        "instance_hypernym" => Some(&mut synset.instance_hypernym),
+        "mero_member" => Some(&mut synset.mero_member),
+        "mero_part" => Some(&mut synset.mero_part),
+        "mero_substance" => Some(&mut synset.mero_substance),
+        "similar" => Some(&mut synset.similar),
+        "has_domain_topic" => Some(&mut synset.has_domain_topic),
+        _ => None
+    }
+}
+
+fn sense_relation<'b>(sense : &'b mut Sense, rel : &str) -> Option<&'b mut Vec<SenseId>> {
+    match rel {
+        "antonym" => Some(&mut sense.antonym),
+        "participle" => Some(&mut sense.participle),
+        "pertainym" => Some(&mut sense.pertainym),
+        "derivation" => Some(&mut sense.derivation),
+        "exemplifies_sense" => Some(&mut sense.exemplifies),
+        "agent" => Some(&mut sense.agent),
+        "material" => Some(&mut sense.material),
+        "event" => Some(&mut sense.event),
+        "instrument" => Some(&mut sense.instrument),
+        "location" => Some(&mut sense.location),
+        "by_means_of" => Some(&mut sense.by_means_of),
+        "undergoer" => Some(&mut sense.undergoer),
+        "property" => Some(&mut sense.property),
+        "result" => Some(&mut sense.result),
+        "state" => Some(&mut sense.state),
+        "uses" => Some(&mut sense.uses),
+        "destination" => Some(&mut sense.destination),
+        "body_part" => Some(&mut sense.body_part),
+        "vehicle" => Some(&mut sense.vehicle),
+        _ => None
+    }
+}
+
+/// Every relation of a sense
+fn sense_targets(sense : &mut Sense) -> Vec<&mut Vec<SenseId>> {
+    vec![&mut sense.antonym, &mut sense.also, &mut sense.participle, &mut sense.pertainym,
+        &mut sense.derivation, &mut sense.domain_topic, &mut sense.has_domain_topic,
+        &mut sense.domain_region, &mut sense.has_domain_region, &mut sense.exemplifies,
+        &mut sense.is_exemplified_by, &mut sense.similar, &mut sense.other, &mut sense.agent,
+        &mut sense.material, &mut sense.event, &mut sense.instrument, &mut sense.location,
+        &mut sense.by_means_of, &mut sense.undergoer, &mut sense.property, &mut sense.result,
+        &mut sense.state, &mut sense.uses, &mut sense.destination, &mut sense.body_part,
+        &mut sense.vehicle]
+}
+
+fn lexfile_pos(lexfile : &str) -> Option<PartOfSpeech> {
+    if !LEXFILES.contains(&lexfile) {
+        return None;
+    }
+    match lexfile.split('.').next() {
+        Some("noun") => Some(PartOfSpeech::n),
+        Some("verb") => Some(PartOfSpeech::v),
+        Some("adj") => Some(PartOfSpeech::a),
+        Some("adv") => Some(PartOfSpeech::r),
+        _ => None
+    }
+}
+
+fn ss_type(pos : &PartOfSpeech) -> u32 {
+    match pos {
+        PartOfSpeech::n => 1,
+        PartOfSpeech::v => 2,
+        PartOfSpeech::a => 3,
+        PartOfSpeech::r => 4,
+        PartOfSpeech::s => 5
+    }
+}
+
+fn is_adjective(ss_type : u32) -> bool {
+    ss_type == 3 || ss_type == 5
+}
+
+fn sense_key_lemma(lemma : &str) -> String {
+    lemma.to_lowercase().replace(' ', "_")
+}
+
+/// Split a sense key into its lemma, synset type, lexicographer file and lexical ID
+fn parse_sense_key(key : &str) -> Option<(&str, u32, u32, u32)> {
+    let (lemma, rest) = key.split_once('%')?;
+    let mut parts = rest.split(':');
+    let ss_type = parts.next()?.parse().ok()?;
+    let lexfile = parts.next()?.parse().ok()?;
+    let lex_id = parts.next()?.parse().ok()?;
+    Some((lemma, ss_type, lexfile, lex_id))
+}
+
+fn non_empty(value : &Option<String>) -> Option<String> {
+    value.as_ref().map(|x| x.trim().to_string()).filter(|x| !x.is_empty())
+}
+
+/// Read a YAML file, returning its value and its text
+fn read_yaml<T : for<'de> Deserialize<'de>>(path : &Path, file_name : &str) -> Result<(T, String), WordNetYAMLIOError> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| WordNetYAMLIOError::Io(format!("Error reading {} due to {}", file_name, e)))?;
+    let value = serde_yaml::from_str(&text)
+        .map_err(|e| WordNetYAMLIOError::Serde(format!("Error reading {} due to {}", file_name, e)))?;
+    Ok((value, text))
+}
+
+/// Serialize a file that was read from `original`, so that a change only
+/// rewrites the lines it touches: the text of each top-level key whose value
+/// is unchanged is kept as it is, and a changed value keeps the order of its
+/// keys and the keys that the structs do not read. New keys are placed in
+/// alphabetical order, as the OEWN sources are sorted.
+fn to_yaml<T : Serialize + for<'de> Deserialize<'de>>(file_name : &str, original : &str, value : &T)
+    -> Result<Vec<u8>, WordNetYAMLIOError> {
+    let error = |e : serde_yaml::Error| WordNetYAMLIOError::Serde(format!("Error writing {} due to {}", file_name, e));
+    let old : T = serde_yaml::from_str(original).map_err(error)?;
+    let old = as_mapping(serde_yaml::to_value(&old).map_err(error)?);
+    let new = as_mapping(serde_yaml::to_value(value).map_err(error)?);
+    let source = as_mapping(serde_yaml::from_str(original).map_err(error)?);
+    let (preamble, blocks) = top_level_blocks(original);
+    if blocks.len() != source.len() {
+        // The keys could not be found in the text, so the whole file is written
+        let merged = merge(&Value::Mapping(source), &Value::Mapping(old), &Value::Mapping(new));
+        return serde_yaml::to_string(&merged).map(|s| s.into_bytes()).map_err(error);
+    }
+    let old : HashMap<String, &Value> = old.iter().map(|(k, v)| (key_str(k), v)).collect();
+    let source_keys : HashSet<String> = source.keys().map(key_str).collect();
+    let mut added : Vec<(String, &Value, &Value)> = new.iter()
+        .map(|(k, v)| (key_str(k), k, v))
+        .filter(|(k, _, _)| !source_keys.contains(k))
+        .collect();
+    added.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut added = added.into_iter().peekable();
+    let new : HashMap<String, &Value> = new.iter().map(|(k, v)| (key_str(k), v)).collect();
+    let mut out = preamble.to_string();
+    for ((key, source_value), text) in source.iter().zip(blocks) {
+        let name = key_str(key);
+        while let Some((_, k, v)) = added.next_if(|(k, _, _)| *k < name) {
+            out.push_str(&single_key(k, v).map_err(error)?);
+        }
+        match (old.get(&name), new.get(&name)) {
+            (_, None) => (),
+            (Some(a), Some(b)) if a == b => out.push_str(text),
+            (Some(a), Some(b)) => out.push_str(&single_key(key, &merge(source_value, a, b)).map_err(error)?),
+            (None, Some(b)) => out.push_str(&single_key(key, b).map_err(error)?)
+        }
+    }
+    for (_, k, v) in added {
+        out.push_str(&single_key(k, v).map_err(error)?);
+    }
+    if new.is_empty() && out.trim().is_empty() {
+        out.push_str("{}\n");
+    }
+    Ok(out.into_bytes())
+}
+
+fn as_mapping(value : Value) -> Mapping {
+    match value {
+        Value::Mapping(mapping) => mapping,
+        _ => Mapping::new()
+    }
+}
+
+fn key_str(key : &Value) -> String {
+    match key {
+        Value::String(s) => s.clone(),
+        other => serde_yaml::to_string(other).unwrap_or_default().trim_end().to_string()
+    }
+}
+
+fn single_key(key : &Value, value : &Value) -> Result<String, serde_yaml::Error> {
+    let mut mapping = Mapping::new();
+    mapping.insert(key.clone(), value.clone());
+    serde_yaml::to_string(&mapping)
+}
+
+/// Split a YAML mapping into the text before its first key and the text of
+/// each top-level key, which starts at the beginning of a line
+fn top_level_blocks(text : &str) -> (&str, Vec<&str>) {
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if line.starts_with(|c : char| !c.is_whitespace() && c != '#')
+            && !line.starts_with("---") && !line.starts_with("...") {
+            starts.push(offset);
+        }
+        offset += line.len();
+    }
+    let preamble = &text[..starts.first().copied().unwrap_or(text.len())];
+    let blocks = starts.iter().enumerate()
+        .map(|(i, start)| &text[*start..starts.get(i + 1).copied().unwrap_or(text.len())])
+        .collect();
+    (preamble, blocks)
+}
+
+/// Apply the difference between the old and new values of the structs to the
+/// value read from the file
+fn merge(source : &Value, old : &Value, new : &Value) -> Value {
+    if old == new {
+        return source.clone();
+    }
+    match (source, old, new) {
+        (Value::Mapping(s), Value::Mapping(a), Value::Mapping(b)) => {
+            let mut entries : Vec<(Value, Value)> = Vec::new();
+            for (k, v) in s.iter() {
+                match (a.get(k), b.get(k)) {
+                    (Some(_), None) => (),
+                    (Some(av), Some(bv)) => entries.push((k.clone(), merge(v, av, bv))),
+                    (None, Some(bv)) => entries.push((k.clone(), bv.clone())),
+                    (None, None) => entries.push((k.clone(), v.clone()))
+                }
+            }
+            let sorted = s.keys().map(key_str).collect::<Vec<_>>().windows(2).all(|w| w[0] <= w[1]);
+            for (k, v) in b.iter().filter(|(k, _)| !s.contains_key(*k)) {
+                let at = if sorted {
+                    entries.iter().position(|(e, _)| key_str(e) > key_str(k)).unwrap_or(entries.len())
+                } else {
+                    entries.len()
+                };
+                entries.insert(at, (k.clone(), v.clone()));
+            }
+            Value::Mapping(entries.into_iter().collect())
+        },
+        (Value::Sequence(s), Value::Sequence(a), Value::Sequence(b)) if s.len() == a.len() => {
+            Value::Sequence(b.iter().enumerate().map(|(i, v)| match a.iter().position(|x| x == v) {
+                Some(j) => s[j].clone(),
+                None if i < a.len() => merge(&s[i], &a[i], v),
+                None => v.clone()
+            }).collect())
+        },
+        _ => new.clone()
+    }
+}
+
+/// Write each file to a temporary file beside it, and only replace the
+/// originals once every temporary file is written
+fn write_files(files : &[(PathBuf, String, Vec<u8>)]) -> Result<(), WordNetYAMLIOError> {
+    let temporary : Vec<PathBuf> = files.iter()
+        .map(|(path, file_name, _)| path.with_file_name(format!(".{}.tmp", file_name)))
+        .collect();
+    for ((_, file_name, content), tmp) in files.iter().zip(temporary.iter()) {
+        if let Err(e) = fs::write(tmp, content) {
+            for tmp in temporary.iter() {
+                fs::remove_file(tmp).ok();
+            }
+            return Err(WordNetYAMLIOError::Io(format!("Error writing {} due to {}", file_name, e)));
+        }
+    }
+    for ((path, file_name, _), tmp) in files.iter().zip(temporary.iter()) {
+        fs::rename(tmp, path)
+            .map_err(|e| WordNetYAMLIOError::Io(format!("Error writing {} due to {}", file_name, e)))?;
+    }
+    Ok(())
+}
+
+#[derive(Error,Debug)]
+pub enum ChangeError {
+    #[error("Invalid change: {0}")]
+    Invalid(String),
+    #[error("{0}")]
+    Yaml(#[from] WordNetYAMLIOError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small source folder in the layout and style of the OEWN YAML files,
+    /// with a key (`note`) that the structs do not read
+    const FILES : &[(&str, &str)] = &[
+        ("noun.Tops.yaml", "00001740-n:
+  definition:
+  - that which is perceived or known or inferred to have its own distinct existence
+  ili: i35545
+  members:
+  - entity
+  partOfSpeech: n
+"),
+        ("noun.animal.yaml", "02084071-n:
+  definition:
+  - a member of the genus Canis
+  example:
+  - the dog barked all night
+  hypernym:
+  - 00001740-n
+  ili: i46360
+  members:
+  - dog
+  - domestic dog
+  note: kept by the editor
+  partOfSpeech: n
+02121620-n:
+  definition:
+  - feline mammal usually having thick soft fur
+  hypernym:
+  - 00001740-n
+  ili: i46593
+  members:
+  - cat
+  partOfSpeech: n
+"),
+        ("entries-c.yaml", "cat:
+  n:
+    sense:
+    - id: 'cat%1:05:00::'
+      similar:
+      - 'dog%1:05:00::'
+      synset: 02121620-n
+"),
+        ("entries-d.yaml", "dog:
+  n:
+    pronunciation:
+    - value: dɔɡ
+    sense:
+    - also:
+      - 'cat%1:05:00::'
+      id: 'dog%1:05:00::'
+      synset: 02084071-n
+domestic dog:
+  n:
+    sense:
+    - id: 'domestic_dog%1:05:00::'
+      synset: 02084071-n
+"),
+        ("entries-e.yaml", "entity:
+  n:
+    sense:
+    - id: 'entity%1:03:00::'
+      synset: 00001740-n
+"),
+    ];
+
+    /// Write the sources to a temporary folder and load the lexicon
+    fn fixture(name : &str) -> (PathBuf, Lexicon) {
+        let dir = std::env::temp_dir().join(format!("oewn-test-{}-{}", std::process::id(), name));
+        fs::remove_dir_all(&dir).ok();
+        let folder = dir.join("yaml");
+        fs::create_dir_all(&folder).unwrap();
+        for (file, content) in FILES {
+            fs::write(folder.join(file), content).unwrap();
+        }
+        let wn = Lexicon::load(&folder, dir.join("wn").to_str().unwrap()).unwrap();
+        (folder, wn)
+    }
+
+    fn read(folder : &Path, file : &str) -> String {
+        fs::read_to_string(folder.join(file)).unwrap()
+    }
+
+    fn original(file : &str) -> &'static str {
+        FILES.iter().find(|(f, _)| *f == file).unwrap().1
+    }
+
+    #[test]
+    fn unchanged_files_are_identical() {
+        for (file, content) in FILES {
+            let written = if file.starts_with("entries-") {
+                to_yaml(file, content, &serde_yaml::from_str::<Entries>(content).unwrap()).unwrap()
+            } else {
+                to_yaml(file, content, &serde_yaml::from_str::<Synsets>(content).unwrap()).unwrap()
+            };
+            assert_eq!(String::from_utf8(written).unwrap(), *content);
+        }
+        let (folder, wn) = fixture("changes-unchanged");
+        let mut workspace = Workspace::new(&folder, &wn, Edits::default());
+        for lexname in ["noun.Tops", "noun.animal"] {
+            workspace.synsets_mut(lexname).unwrap();
+        }
+        for lemma in ["cat", "dog", "entity"] {
+            workspace.entries_mut(lemma).unwrap();
+        }
+        let (written, _) = workspace.save().unwrap();
+        assert!(written.is_empty());
+        for (file, content) in FILES {
+            assert_eq!(read(&folder, file), *content);
+        }
+    }
+
+    #[test]
+    fn change_only_rewrites_its_lines() {
+        let (folder, wn) = fixture("changes-definition");
+        let written = apply_changes(&folder, &wn, &mut Edits::default(), &[Change::ChangeDefinition {
+            synset: "oewn-02084071-n".to_string(),
+            definition: "a member of the genus Canis, kept as a pet".to_string()
+        }]).unwrap();
+        assert_eq!(written, vec!["noun.animal.yaml".to_string()]);
+        assert_eq!(read(&folder, "noun.animal.yaml"), original("noun.animal.yaml")
+            .replace("- a member of the genus Canis\n", "- a member of the genus Canis, kept as a pet\n"));
+    }
+
+    const DOG : &str = "02084071-n";
+    const CAT : &str = "02121620-n";
+
+    fn apply(folder : &Path, wn : &Lexicon, changes : Vec<Change>) -> Result<Vec<String>, ChangeError> {
+        apply_changes(folder, wn, &mut Edits::default(), &changes)
+    }
+
+    fn is_invalid(result : Result<Vec<String>, ChangeError>) -> bool {
+        matches!(result, Err(ChangeError::Invalid(_)))
+    }
+
+    fn synset(folder : &Path, id : &str) -> Option<Synset> {
+        let lexname = if id == "00001740-n" { "noun.Tops" } else { "noun.animal" };
+        let synsets : Synsets = serde_yaml::from_str(&read(folder, &format!("{}.yaml", lexname))).unwrap();
+        synsets.0.get(&SynsetId::new(id)).cloned()
+    }
+
+    fn senses(folder : &Path, lemma : &str) -> Vec<Sense> {
+        let entries : Entries = serde_yaml::from_str(&read(folder, &format!("entries-{}.yaml", entry_key(lemma)))).unwrap();
+        entries.0.get(lemma).into_iter()
+            .flat_map(|by_pos| by_pos.values())
+            .flat_map(|entry| entry.sense.iter().cloned())
+            .collect()
+    }
+
+    #[test]
+    fn change_definition() {
+        let (folder, wn) = fixture("changes-definition-errors");
+        assert!(is_invalid(apply(&folder, &wn, vec![Change::ChangeDefinition {
+            synset: DOG.to_string(), definition: " ".to_string() }])));
+        assert!(is_invalid(apply(&folder, &wn, vec![Change::ChangeDefinition {
+            synset: "99999999-n".to_string(), definition: "nothing".to_string() }])));
+        assert!(is_invalid(apply(&folder, &wn, vec![Change::ChangeDefinition {
+            synset: "last".to_string(), definition: "nothing".to_string() }])));
+    }
+
+    #[test]
+    fn change_ili() {
+        let (folder, wn) = fixture("changes-ili");
+        assert!(is_invalid(apply(&folder, &wn, vec![Change::ChangeIli {
+            synset: DOG.to_string(), ili: Some("x46360".to_string()) }])));
+        apply(&folder, &wn, vec![Change::ChangeIli { synset: DOG.to_string(), ili: Some("in".to_string()) }]).unwrap();
+        assert_eq!(synset(&folder, DOG).unwrap().ili, Some(ILIID::new("in")));
+        apply(&folder, &wn, vec![Change::ChangeIli { synset: DOG.to_string(), ili: None }]).unwrap();
+        assert_eq!(synset(&folder, DOG).unwrap().ili, None);
+        assert!(read(&folder, "noun.animal.yaml").contains("note: kept by the editor"));
+    }
+
+    #[test]
+    fn change_wikidata_and_source() {
+        let (folder, wn) = fixture("changes-wikidata");
+        apply(&folder, &wn, vec![
+            Change::ChangeWikidata { synset: DOG.to_string(), wikidata: Some(" Q144 ".to_string()) },
+            Change::ChangeSource { synset: DOG.to_string(), source: Some("PWN".to_string()) }]).unwrap();
+        let dog = synset(&folder, DOG).unwrap();
+        assert_eq!(dog.wikidata, Some("Q144".to_string()));
+        assert_eq!(dog.source, Some("PWN".to_string()));
+        let written = apply(&folder, &wn, vec![Change::ChangeSource { synset: CAT.to_string(), source: Some("".to_string()) }]).unwrap();
+        assert!(written.is_empty());
+    }
+
+    #[test]
+    fn change_members() {
+        let (folder, wn) = fixture("changes-members");
+        assert!(is_invalid(apply(&folder, &wn, vec![Change::ChangeMembers {
+            synset: DOG.to_string(), members: Vec::new() }])));
+        assert!(is_invalid(apply(&folder, &wn, vec![Change::ChangeMembers {
+            synset: DOG.to_string(), members: vec!["dog".to_string(), "".to_string()] }])));
+        apply(&folder, &wn, vec![Change::ChangeMembers {
+            synset: DOG.to_string(), members: vec!["dog".to_string(), "doggy".to_string()] }]).unwrap();
+        assert_eq!(synset(&folder, DOG).unwrap().members, vec!["dog".to_string(), "doggy".to_string()]);
+        assert!(senses(&folder, "domestic dog").is_empty());
+        let doggy = senses(&folder, "doggy");
+        assert_eq!(doggy.len(), 1);
+        assert_eq!(doggy[0].id, SenseId::new("doggy%1:05:00::"));
+        assert_eq!(doggy[0].synset, SynsetId::new(DOG));
+    }
+
+    #[test]
+    fn update_relations() {
+        let (folder, wn) = fixture("changes-relations");
+        let mut relations : Vec<RelationChange> = member_relations(&wn.synset_by_id(&SynsetId::new(CAT)).unwrap())
+            .into_iter()
+            .filter(|r| r.relation != "hypernym")
+            .collect();
+        relations.push(RelationChange { target: SynsetId::new(DOG), relation: "hypernym".to_string(),
+            source_lemma: None, target_lemma: None });
+        relations.push(RelationChange { target: SynsetId::new(DOG), relation: "antonym".to_string(),
+            source_lemma: Some("cat".to_string()), target_lemma: Some("dog".to_string()) });
+        let mut unknown = relations.clone();
+        unknown.push(RelationChange { target: SynsetId::new(DOG), relation: "friend".to_string(),
+            source_lemma: None, target_lemma: None });
+        assert!(is_invalid(apply(&folder, &wn, vec![Change::UpdateRelations { synset: CAT.to_string(), relations: unknown }])));
+        let mut no_lemma = relations.clone();
+        no_lemma.last_mut().unwrap().target_lemma = Some(" ".to_string());
+        assert!(is_invalid(apply(&folder, &wn, vec![Change::UpdateRelations { synset: CAT.to_string(), relations: no_lemma }])));
+        apply(&folder, &wn, vec![Change::UpdateRelations { synset: CAT.to_string(), relations }]).unwrap();
+        assert_eq!(synset(&folder, CAT).unwrap().hypernym, vec![SynsetId::new(DOG)]);
+        assert_eq!(senses(&folder, "cat")[0].antonym, vec![SenseId::new("dog%1:05:00::")]);
+        assert_eq!(senses(&folder, "dog")[0].antonym, vec![SenseId::new("cat%1:05:00::")]);
+    }
+
+    #[test]
+    fn add_and_delete_examples() {
+        let (folder, wn) = fixture("changes-examples");
+        assert!(is_invalid(apply(&folder, &wn, vec![Change::AddExample {
+            synset: DOG.to_string(), example: "".to_string() }])));
+        assert!(is_invalid(apply(&folder, &wn, vec![Change::DeleteExample {
+            synset: DOG.to_string(), number: 1 }])));
+        apply(&folder, &wn, vec![
+            Change::AddExample { synset: DOG.to_string(), example: "the dog slept".to_string() },
+            Change::DeleteExample { synset: DOG.to_string(), number: 0 }]).unwrap();
+        assert_eq!(synset(&folder, DOG).unwrap().example,
+            vec![Example { text: "the dog slept".to_string(), source: None }]);
+    }
+
+    #[test]
+    fn delete_synset() {
+        let (folder, wn) = fixture("changes-delete");
+        let delete = |id : &str, by : Option<&str>| Change::DeleteSynset { synset: id.to_string(),
+            reason: "duplicate".to_string(), superseded_by: by.map(|x| x.to_string()) };
+        assert!(is_invalid(apply(&folder, &wn, vec![delete(CAT, Some(CAT))])));
+        assert!(is_invalid(apply(&folder, &wn, vec![delete(CAT, None), delete(CAT, None)])));
+        for (file, content) in FILES {
+            assert_eq!(read(&folder, file), *content);
+        }
+        apply(&folder, &wn, vec![delete(CAT, Some(DOG))]).unwrap();
+        assert!(synset(&folder, CAT).is_none());
+        assert!(senses(&folder, "cat").is_empty());
+        // The `also` of dog pointed to the sense of cat
+        assert!(senses(&folder, "dog")[0].also.is_empty());
+        assert_eq!(fs::read_to_string(folder.parent().unwrap().join("deprecations.csv")).unwrap(),
+            "\"oewn-02121620-n\",\"i46593\",\"oewn-02084071-n\",\"i46360\",\"duplicate\"\n");
+    }
+
+    #[test]
+    fn add_synset() {
+        let (folder, wn) = fixture("changes-add");
+        let add = |lexfile : &str, lemmas : Vec<&str>| Change::AddSynset { definition: "a young dog".to_string(),
+            lexfile: lexfile.to_string(), lemmas: lemmas.into_iter().map(|x| x.to_string()).collect() };
+        assert!(is_invalid(apply(&folder, &wn, vec![add("noun.pet", vec!["doggy"])])));
+        assert!(is_invalid(apply(&folder, &wn, vec![add("noun.animal", Vec::new())])));
+        apply(&folder, &wn, vec![add("noun.animal", vec!["doggy"]),
+            Change::ChangeIli { synset: "last".to_string(), ili: Some("in".to_string()) }]).unwrap();
+        let new = synset(&folder, "02121621-n").unwrap();
+        assert_eq!(new.members, vec!["doggy".to_string()]);
+        assert_eq!(new.ili, Some(ILIID::new("in")));
+        assert_eq!(new.part_of_speech, PartOfSpeech::n);
+        let doggy = senses(&folder, "doggy");
+        assert_eq!(doggy[0].id, SenseId::new("doggy%1:05:00::"));
+        assert_eq!(doggy[0].synset, SynsetId::new("02121621-n"));
+    }
+}
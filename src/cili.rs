@@ -0,0 +1,208 @@
+/// The Collaborative Interlingual Index (CILI), which gives the definition
+/// and status of each ILI
+///
+
+use crate::wordnet::{ILIID, Lexicon, MemberSynset, SynsetId};
+use flate2::read::GzDecoder;
+use oxigraph::io::{RdfFormat, RdfParser};
+use oxigraph::model::{Subject, Term};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use thiserror::Error;
+
+const RDF_TYPE : &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const SKOS_DEFINITION : &str = "http://www.w3.org/2004/02/skos/core#definition";
+const OWL_DEPRECATED : &str = "http://www.w3.org/2002/07/owl#deprecated";
+const DCT_IS_REPLACED_BY : &str = "http://purl.org/dc/terms/isReplacedBy";
+
+/// CILI does not accept shorter definitions for new concepts
+const MIN_DEFINITION_LENGTH : usize = 20;
+
+/// What CILI says about an ILI
+#[derive(Serialize, Default, Clone)]
+pub struct IliEntry {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub definition : Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated : bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replaced_by : Option<String>,
+}
+
+/// The entries of CILI by ILI
+#[derive(Default)]
+pub struct Cili(HashMap<String, IliEntry>);
+
+impl Cili {
+    /// Load the CILI `ili.ttl`, which may be gzipped
+    pub fn load(file : &Path) -> Result<Cili, CiliError> {
+        let file_name = file.file_name().and_then(|x| x.to_str()).unwrap_or("").to_string();
+        let input = File::open(file)
+            .map_err(|e| CiliError::Io(format!("Error reading {} due to {}", file_name, e)))?;
+        let input : Box<dyn Read> = if file_name.ends_with(".gz") {
+            Box::new(GzDecoder::new(input))
+        } else {
+            Box::new(input)
+        };
+        let mut entries : HashMap<String, IliEntry> = HashMap::new();
+        for quad in RdfParser::from_format(RdfFormat::Turtle).for_reader(BufReader::new(input)) {
+            let quad = quad.map_err(|e| CiliError::Syntax(format!("{}: {}", file_name, e)))?;
+            let ili = match &quad.subject {
+                Subject::NamedNode(node) => local_name(node.as_str()),
+                _ => continue
+            };
+            if ILIID::parse(ili).is_err() {
+                continue;
+            }
+            let entry = entries.entry(ili.to_string()).or_default();
+            match (quad.predicate.as_str(), &quad.object) {
+                (SKOS_DEFINITION, Term::Literal(literal)) => {
+                    entry.definition = Some(literal.value().to_string());
+                },
+                (RDF_TYPE, Term::NamedNode(node)) if node.as_str().ends_with("Deprecated") => {
+                    entry.deprecated = true;
+                },
+                (OWL_DEPRECATED, Term::Literal(literal)) if literal.value() == "true" => {
+                    entry.deprecated = true;
+                },
+                (DCT_IS_REPLACED_BY, Term::NamedNode(node)) => {
+                    entry.deprecated = true;
+                    entry.replaced_by = Some(local_name(node.as_str()).to_string());
+                },
+                _ => ()
+            }
+        }
+        Ok(Cili(entries))
+    }
+
+    /// Look up an ILI
+    pub fn get(&self, ili : &str) -> Option<&IliEntry> {
+        self.0.get(ili)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+fn local_name(iri : &str) -> &str {
+    iri.rsplit(['/', '#']).next().unwrap_or(iri)
+}
+
+/// The problems with the ILIs of a lexicon
+#[derive(Serialize)]
+pub struct IliReport {
+    /// The synsets marked `in`, which will be submitted as new concepts
+    pub proposed : Vec<ProposedIli>,
+    /// Synsets linked to an ILI that CILI has deprecated
+    pub deprecated : Vec<LinkedIli>,
+    /// Synsets linked to an ILI that CILI does not have
+    pub unknown : Vec<LinkedIli>,
+    /// Synsets whose ILI is not `in` or `i` followed by a number
+    pub invalid : Vec<LinkedIli>,
+}
+
+#[derive(Serialize)]
+pub struct ProposedIli {
+    pub id : SynsetId,
+    pub definition : String,
+    /// Why CILI would reject the proposal, empty if there is no problem
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub problems : Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct LinkedIli {
+    pub ili : String,
+    pub id : SynsetId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replaced_by : Option<String>,
+}
+
+/// Check the synsets proposed for a new ILI and, if CILI is loaded, the
+/// existing ILIs of the lexicon
+pub fn report(wn : &Lexicon, cili : &Cili) -> IliReport {
+    let proposed : Vec<MemberSynset> = wn.synsets_with_new_ili().iter()
+        .filter_map(|id| wn.synset_by_id(id))
+        .collect();
+    let mut proposed_definitions : HashMap<String, Vec<&SynsetId>> = HashMap::new();
+    for synset in proposed.iter() {
+        if let Some(definition) = synset.definition.first() {
+            proposed_definitions.entry(normalize(definition)).or_default().push(&synset.id);
+        }
+    }
+    let cili_definitions : HashMap<String, &String> = cili.0.iter()
+        .filter_map(|(ili, entry)| entry.definition.as_ref().map(|d| (normalize(d), ili)))
+        .collect();
+    let mut report = IliReport {
+        proposed: Vec::new(),
+        deprecated: Vec::new(),
+        unknown: Vec::new(),
+        invalid: Vec::new()
+    };
+    for synset in proposed.iter() {
+        let definition = synset.definition.first().cloned().unwrap_or_default();
+        let key = normalize(&definition);
+        let mut problems = Vec::new();
+        if key.is_empty() {
+            problems.push("The synset has no definition".to_string());
+        } else {
+            if definition.trim().chars().count() < MIN_DEFINITION_LENGTH {
+                problems.push(format!("The definition is shorter than {} characters", MIN_DEFINITION_LENGTH));
+            }
+            if let Some(ili) = cili_definitions.get(&key) {
+                problems.push(format!("The definition is the same as that of {}", ili));
+            }
+            for other in proposed_definitions.get(&key).into_iter().flatten() {
+                if **other != synset.id {
+                    problems.push(format!("The definition is the same as that of synset {}", other.as_str()));
+                }
+            }
+        }
+        report.proposed.push(ProposedIli {
+            id: synset.id.clone(),
+            definition,
+            problems
+        });
+    }
+    for synset in wn.synsets_with_invalid_ili().iter().filter_map(|id| wn.synset_by_id(id)) {
+        report.invalid.push(LinkedIli {
+            ili: synset.ili.as_ref().map(|i| i.as_str().to_string()).unwrap_or_default(),
+            id: synset.id.clone(),
+            replaced_by: None
+        });
+    }
+    if !cili.is_empty() {
+        for (ili, id) in wn.ilis() {
+            match cili.get(ili) {
+                None => report.unknown.push(LinkedIli {
+                    ili: ili.to_string(),
+                    id: id.clone(),
+                    replaced_by: None
+                }),
+                Some(entry) if entry.deprecated => report.deprecated.push(LinkedIli {
+                    ili: ili.to_string(),
+                    id: id.clone(),
+                    replaced_by: entry.replaced_by.clone()
+                }),
+                _ => ()
+            }
+        }
+    }
+    report
+}
+
+fn normalize(definition : &str) -> String {
+    definition.trim().trim_end_matches('.').to_lowercase()
+}
+
+#[derive(Error, Debug)]
+pub enum CiliError {
+    #[error("Could not load CILI: {0}")]
+    Io(String),
+    #[error("Could not load CILI: {0}")]
+    Syntax(String),
+}
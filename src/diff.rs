@@ -0,0 +1,250 @@
+/// Differences between two editions of the wordnet
+///
+
+use crate::wordnet::{ILIID, Lexicon, MemberSynset, SenseId, SynsetId};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// The changes that turn one lexicon into another
+#[derive(Serialize, Default)]
+pub struct LexiconDiff {
+    pub added : Vec<SynsetId>,
+    pub removed : Vec<SynsetId>,
+    pub changed : Vec<SynsetDiff>,
+    /// ILIs that moved to a synset with a different ID
+    pub ili : Vec<IliReassignment>,
+}
+
+/// The changes to a synset that is in both lexicons
+#[derive(Serialize)]
+pub struct SynsetDiff {
+    pub id : SynsetId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub definition : Option<Change<Vec<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ili : Option<Change<Option<ILIID>>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added_members : Vec<MemberChange>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed_members : Vec<MemberChange>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub added_relations : Vec<RelationChange>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed_relations : Vec<RelationChange>,
+}
+
+#[derive(Serialize)]
+pub struct Change<T> {
+    pub from : T,
+    pub to : T,
+}
+
+#[derive(Serialize, PartialEq, Clone)]
+pub struct MemberChange {
+    pub sense : SenseId,
+    pub lemma : String,
+}
+
+/// A synset relation, or a sense relation if there is a source sense
+#[derive(Serialize, PartialEq, Clone)]
+pub struct RelationChange {
+    #[serde(rename = "relType")]
+    pub rel_type : &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source : Option<SenseId>,
+    pub target : SynsetId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_lemma : Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct IliReassignment {
+    pub ili : String,
+    pub from : SynsetId,
+    pub to : SynsetId,
+}
+
+/// Compare two lexicons
+pub fn diff(from : &Lexicon, to : &Lexicon) -> LexiconDiff {
+    let from_ids : HashSet<&SynsetId> = from.synset_ids.iter().collect();
+    let to_ids : HashSet<&SynsetId> = to.synset_ids.iter().collect();
+    let mut diff = LexiconDiff::default();
+    for id in from.synset_ids.iter() {
+        if !to_ids.contains(id) {
+            diff.removed.push(id.clone());
+        }
+    }
+    for id in to.synset_ids.iter() {
+        let synset = match to.synset_by_id(id) {
+            Some(synset) => synset,
+            None => continue
+        };
+        if !from_ids.contains(id) {
+            diff.added.push(id.clone());
+        } else if let Some(old) = from.synset_by_id(id) {
+            diff.changed.extend(diff_synset(&old, &synset));
+        }
+        if let Some(ili) = synset.ili.as_ref() {
+            match from.synset_id_by_ili(ili.as_str()) {
+                Some(old) if old != id => diff.ili.push(IliReassignment {
+                    ili: ili.as_str().to_string(),
+                    from: old.clone(),
+                    to: id.clone()
+                }),
+                _ => ()
+            }
+        }
+    }
+    diff.added.sort();
+    diff.removed.sort();
+    diff.changed.sort_by(|a, b| a.id.cmp(&b.id));
+    diff.ili.sort_by(|a, b| a.ili.cmp(&b.ili));
+    diff
+}
+
+/// Compare two versions of a synset, returning `None` if nothing changed
+pub fn diff_synset(from : &MemberSynset, to : &MemberSynset) -> Option<SynsetDiff> {
+    let from_members = members(from);
+    let to_members = members(to);
+    let from_relations = relations(from);
+    let to_relations = relations(to);
+    let diff = SynsetDiff {
+        id: to.id.clone(),
+        definition: if from.definition != to.definition {
+            Some(Change { from: from.definition.clone(), to: to.definition.clone() })
+        } else {
+            None
+        },
+        ili: if from.ili != to.ili {
+            Some(Change { from: from.ili.clone(), to: to.ili.clone() })
+        } else {
+            None
+        },
+        added_members: to_members.iter().filter(|m| !from_members.contains(m)).cloned().collect(),
+        removed_members: from_members.iter().filter(|m| !to_members.contains(m)).cloned().collect(),
+        added_relations: to_relations.iter().filter(|r| !from_relations.contains(r)).cloned().collect(),
+        removed_relations: from_relations.iter().filter(|r| !to_relations.contains(r)).cloned().collect(),
+    };
+    if diff.definition.is_none() && diff.ili.is_none() && diff.added_members.is_empty() &&
+        diff.removed_members.is_empty() && diff.added_relations.is_empty() &&
+        diff.removed_relations.is_empty() {
+        None
+    } else {
+        Some(diff)
+    }
+}
+
+fn members(synset : &MemberSynset) -> Vec<MemberChange> {
+    synset.members.iter().map(|m| MemberChange {
+        sense: m.sense.id.clone(),
+        lemma: m.lemma.clone()
+    }).collect()
+}
+
+fn relations(synset : &MemberSynset) -> Vec<RelationChange> {
+    let mut relations : Vec<RelationChange> = synset.relations().into_iter()
+        .map(|(rel_type, target)| RelationChange {
+            rel_type,
+            source: None,
+            target: target.clone(),
+            target_lemma: None
+        }).collect();
+    for (rel_type, relation) in synset.sense_relations() {
+        relations.push(RelationChange {
+            rel_type,
+            source: synset.members.iter()
+                .find(|m| m.lemma == relation.source_lemma)
+                .map(|m| m.sense.id.clone()),
+            target: relation.target_synset.clone(),
+            target_lemma: Some(relation.target_lemma.clone())
+        });
+    }
+    relations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wordnet::tests::{TAXONOMY, lexicon_from_lmf};
+
+    const CAT_ENTRY : &str = r#"    <LexicalEntry id="oewn-cat-n">
+      <Lemma writtenForm="cat" partOfSpeech="n"/>
+      <Sense id="oewn-cat__1.05.00.." dc:identifier="cat%1:05:00::" synset="oewn-00000004-n"/>
+    </LexicalEntry>
+"#;
+
+    const CAT_SYNSET : &str = r#"    <Synset id="oewn-00000004-n" ili="i46593" partOfSpeech="n" lexfile="noun.animal">
+      <Definition>a feline mammal</Definition>
+      <SynsetRelation relType="hypernym" target="oewn-00000002-n"/>
+    </Synset>
+"#;
+
+    /// The test taxonomy without the cat, with a hound that is a dog and a
+    /// new wolf synset, the puppy moved under animal, a new definition of
+    /// entity and eat under a new ID
+    fn edited() -> String {
+        assert!(TAXONOMY.contains(CAT_ENTRY) && TAXONOMY.contains(CAT_SYNSET));
+        TAXONOMY.replace(CAT_ENTRY, "")
+            .replace(CAT_SYNSET, "")
+            .replace("that which exists", "that which is")
+            .replace(r#"<SynsetRelation relType="hypernym" target="oewn-00000003-n"/>"#,
+                r#"<SynsetRelation relType="hypernym" target="oewn-00000002-n"/>"#)
+            .replace("oewn-00000008-v", "oewn-00000011-v")
+            .replace(r#"    <Synset id="oewn-00000001-n""#, r#"    <LexicalEntry id="oewn-hound-n">
+      <Lemma writtenForm="hound" partOfSpeech="n"/>
+      <Sense id="oewn-hound__1.05.00.." dc:identifier="hound%1:05:00::" synset="oewn-00000003-n"/>
+    </LexicalEntry>
+    <LexicalEntry id="oewn-wolf-n">
+      <Lemma writtenForm="wolf" partOfSpeech="n"/>
+      <Sense id="oewn-wolf__1.05.00.." dc:identifier="wolf%1:05:00::" synset="oewn-00000009-n"/>
+    </LexicalEntry>
+    <Synset id="oewn-00000009-n" ili="" partOfSpeech="n" lexfile="noun.animal">
+      <Definition>a wild canine</Definition>
+      <SynsetRelation relType="hypernym" target="oewn-00000002-n"/>
+    </Synset>
+    <Synset id="oewn-00000001-n""#)
+    }
+
+    fn hypernym(target : &str) -> RelationChange {
+        RelationChange { rel_type: "hypernym", source: None, target: SynsetId::new(target), target_lemma: None }
+    }
+
+    #[test]
+    fn diff_editions() {
+        let from = lexicon_from_lmf("diff-from", TAXONOMY);
+        let to = lexicon_from_lmf("diff-to", &edited());
+        let diff = diff(&from, &to);
+        assert_eq!(diff.added, vec![SynsetId::new("00000009-n"), SynsetId::new("00000011-v")]);
+        assert_eq!(diff.removed, vec![SynsetId::new("00000004-n"), SynsetId::new("00000008-v")]);
+        let changed : Vec<&str> = diff.changed.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(changed, vec!["00000001-n", "00000003-n", "00000005-n"]);
+
+        let entity = &diff.changed[0];
+        let definition = entity.definition.as_ref().unwrap();
+        assert_eq!(definition.from, vec!["that which exists".to_string()]);
+        assert_eq!(definition.to, vec!["that which is".to_string()]);
+        assert!(entity.ili.is_none() && entity.added_members.is_empty() && entity.added_relations.is_empty());
+
+        let dog = &diff.changed[1];
+        assert!(dog.definition.is_none());
+        assert!(dog.added_members == vec![MemberChange { sense: SenseId::new("hound%1:05:00::"), lemma: "hound".to_string() }]);
+        assert!(dog.removed_members.is_empty());
+
+        let puppy = &diff.changed[2];
+        assert!(puppy.added_relations == vec![hypernym("00000002-n")]);
+        assert!(puppy.removed_relations == vec![hypernym("00000003-n")]);
+        assert!(puppy.added_members.is_empty() && puppy.removed_members.is_empty());
+
+        assert_eq!(diff.ili.len(), 1);
+        assert_eq!(diff.ili[0].ili, "i34539");
+        assert_eq!(diff.ili[0].from, SynsetId::new("00000008-v"));
+        assert_eq!(diff.ili[0].to, SynsetId::new("00000011-v"));
+    }
+
+    #[test]
+    fn same_edition() {
+        let from = lexicon_from_lmf("diff-same", TAXONOMY);
+        let diff = diff(&from, &from);
+        assert!(diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty() && diff.ili.is_empty());
+    }
+}
@@ -1,13 +1,106 @@
-/// Handlebars templates
+/// Handlebars templates and the RDF view of synsets
 ///
 
-use crate::wordnet::{SynsetId, ILIID, PartOfSpeech, Pronunciation, Lexicon, MemberSynset, Member};
-use handlebars::{Handlebars, Helper, HelperResult, Output, RenderContext, Context};
+use crate::omw::Translations;
+use crate::rdf::{Graph, JsonLdTerm, JsonLdType, Term, RDF_TYPE, iri_segment};
+use crate::wordnet::{SynsetId, ILIID, PartOfSpeech, Pronunciation, MemberSynset, Member};
 use std::collections::HashMap;
 use serde::{Serialize,Deserialize};
 
-const LICENSE : &'static str = "https://github.com/globalwordnet/english-wordnet/blob/master/LICENSE.md";
-const SITE_URL : &'static str = "https://en-word.net";
+const LICENSE : &str = "https://github.com/globalwordnet/english-wordnet/blob/master/LICENSE.md";
+pub const SITE_URL : &str = "https://en-word.net";
+
+const DC : &str = "http://purl.org/dc/terms/";
+const ILI : &str = "http://ili.globalwordnet.org/ili/";
+const ONTOLEX : &str = "http://www.w3.org/ns/lemon/ontolex#";
+const OWL : &str = "http://www.w3.org/2002/07/owl#";
+const RDFS : &str = "http://www.w3.org/2000/01/rdf-schema#";
+const SKOS : &str = "http://www.w3.org/2004/02/skos/core#";
+const SYNSEM : &str = "http://www.w3.org/ns/lemon/synsem#";
+const VARTRANS : &str = "http://www.w3.org/ns/lemon/vartrans#";
+const WN : &str = "https://globalwordnet.github.io/schemas/wn#";
+const WIKIDATA : &str = "http://www.wikidata.org/entity/";
+
+/// The prefixes used when writing the RDF view
+pub fn namespaces() -> Vec<(String, String)> {
+    vec![
+        ("dc", DC.to_string()),
+        ("ili", ILI.to_string()),
+        ("lime", "http://www.w3.org/ns/lemon/lime#".to_string()),
+        ("ontolex", ONTOLEX.to_string()),
+        ("owl", OWL.to_string()),
+        ("rdf", crate::rdf::RDF.to_string()),
+        ("rdfs", RDFS.to_string()),
+        ("schema", "http://schema.org/".to_string()),
+        ("skos", SKOS.to_string()),
+        ("synsem", SYNSEM.to_string()),
+        ("vartrans", VARTRANS.to_string()),
+        ("wd", WIKIDATA.to_string()),
+        ("wn", WN.to_string()),
+        ("wnlemma", format!("{}/lemma/", SITE_URL)),
+        ("wnid", format!("{}/id/", SITE_URL)),
+    ].into_iter().map(|(p, ns)| (p.to_string(), ns)).collect()
+}
+
+/// The relations written as properties in the `wn` namespace
+const RELATION_TYPES : &[&str] = &["also", "attribute", "causes", "domain_region",
+    "domain_topic", "exemplifies", "entails", "hypernym", "instance_hypernym", "mero_member",
+    "mero_part", "mero_substance", "similar", "hyponym", "is_caused_by", "has_domain_region",
+    "has_domain_topic", "is_exemplified_by", "is_entailed_by", "instance_hyponym", "holo_member",
+    "holo_part", "holo_substance", "antonym", "participle", "is_participle_of", "pertainym",
+    "is_pertainym_of", "derivation", "agent", "involved_agent", "instrument", "involved_instrument",
+    "location", "involved_location"];
+
+/// The JSON-LD terms for the OntoLex-Lemon and GWN properties of the RDF
+/// view. `wn:ili` has no term, as `ili` is the prefix of the ILIs
+pub fn jsonld_terms() -> Vec<JsonLdTerm> {
+    let id = |name : &str, ns : &str| JsonLdTerm::new(name, format!("{}{}", ns, name), JsonLdType::Id);
+    let en = |name : &str, ns : &str| JsonLdTerm::new(name, format!("{}{}", ns, name), JsonLdType::Language("en".to_string()));
+    let mut terms = vec![
+        id("canonicalForm", ONTOLEX),
+        id("sense", ONTOLEX),
+        id("isLexicalizedSenseOf", ONTOLEX),
+        en("writtenRep", ONTOLEX),
+        id("synBehavior", SYNSEM),
+        en("label", RDFS),
+        id("source", VARTRANS),
+        id("target", VARTRANS),
+        id("category", VARTRANS),
+        id("sameAs", OWL),
+        id("exactMatch", SKOS),
+        id("inScheme", SKOS),
+        JsonLdTerm::new("subject", format!("{}subject", DC), JsonLdType::String),
+        id("partOfSpeech", WN),
+        id("definition", WN),
+        id("example", WN),
+        en("value", crate::rdf::RDF),
+    ];
+    terms.extend(RELATION_TYPES.iter().map(|rel| id(rel, WN)));
+    terms
+}
+
+/// The IRI of a synset
+pub fn synset_iri(id : &str) -> String {
+    format!("{}/id/{}", SITE_URL, iri_segment(id))
+}
+
+/// The IRI of the lexical entry for a lemma and part of speech
+pub fn entry_iri(lemma : &str, pos : &str) -> String {
+    let lemma = iri_segment(&lemma.replace(" ", "_"));
+    format!("{}/rdf/lemma/{}#{}-{}", SITE_URL, lemma, lemma, pos)
+}
+
+/// The IRI of the sense of a lemma in a synset
+pub fn sense_iri(lemma : &str, synset : &str) -> String {
+    let lemma = iri_segment(&lemma.replace(" ", "_"));
+    format!("{}/rdf/lemma/{}#{}-{}", SITE_URL, lemma, lemma, iri_segment(synset))
+}
+
+/// The IRI of the lexical entry for a lemma of another language
+fn translation_entry_iri(language : &str, lemma : &str, pos : &str) -> String {
+    let lemma = iri_segment(&lemma.replace(" ", "_"));
+    format!("{}/rdf/{}/lemma/{}#{}-{}", SITE_URL, iri_segment(language), lemma, lemma, pos)
+}
 
 #[derive(Clone,Debug,Serialize,Deserialize)]
 pub struct SynsetsHB {
@@ -20,34 +113,16 @@ pub struct SynsetsHB {
 }
 
 impl SynsetsHB {
-    pub fn all(lexicon : &Lexicon) -> SynsetsHB {
-        let mut synsets = Vec::new();
-        let mut entries = HashMap::new();
-        for synset_id in lexicon.synset_ids.iter() {
-            let synset = lexicon.synset_by_id(synset_id).unwrap();
-            let s2 = HBSynset::from(&synset);
-            for lemma in synset.members.iter() {
-                entries.entry("en".to_string())
-                    .or_insert_with(|| HashMap::new())
-                    .entry(format!("{}-{}", lemma.lemma, synset.part_of_speech.str()))
-                    .or_insert_with(|| Vec::new())
-                    .push(s2.clone());
-            }
-            synsets.push(s2);
-        }
-        SynsetsHB {
-            synsets,
-            entries,
-            index: "id".to_string(),
-            name: "ignored".to_string(),
-            license: LICENSE,
-            site: SITE_URL
+    /// The RDF graph of all the synsets
+    pub fn graph(&self) -> Graph {
+        let mut graph = Graph::new();
+        for synset in self.synsets.iter() {
+            synset.add_to_graph(&mut graph);
         }
-
+        graph
     }
 }
 
-
 #[derive(Clone,Debug,Serialize,Deserialize)]
 pub struct HBSynset {
     pub definition : String,
@@ -55,9 +130,12 @@ pub struct HBSynset {
     pub lemmas : Vec<Sense>,
     pub id : SynsetId,
     pub ili : Option<ILIID>,
+    pub wikidata : Option<String>,
     pub pos : PartOfSpeech,
     pub subject : String,
     pub relations : Vec<Relation>,
+    /// The lemmas of other languages, by language
+    pub translations : Vec<(String, String)>,
 }
 
 impl HBSynset {
@@ -133,9 +211,99 @@ impl HBSynset {
             lemmas,
             id: synset.id.clone(),
             ili: synset.ili.clone(),
+            wikidata: synset.wikidata.clone(),
             pos: synset.part_of_speech.clone(),
             subject: synset.lexname.clone(),
-            relations
+            relations,
+            translations: Vec::new()
+        }
+    }
+
+    /// Add the lemmas of other languages that share the ILI of the synset
+    pub fn with_translations(mut self, translations : &Translations) -> HBSynset {
+        if let Some(by_language) = self.ili.as_ref().and_then(|ili| translations.by_ili(ili.as_str())) {
+            for (language, lemmas) in by_language.iter() {
+                self.translations.extend(lemmas.iter().map(|l| (language.clone(), l.clone())));
+            }
+        }
+        self
+    }
+
+    /// Add the triples for the synset, its senses and their lexical entries
+    pub fn add_to_graph(&self, graph : &mut Graph) {
+        let pos = self.pos.str();
+        let synset = Term::iri(synset_iri(self.id.as_str()));
+        for sense in self.lemmas.iter() {
+            let entry = Term::iri(entry_iri(&sense.lemma, pos));
+            let form = Term::iri(format!("{}-form", entry_iri(&sense.lemma, pos)));
+            let sense_node = Term::iri(sense_iri(&sense.lemma, self.id.as_str()));
+            graph.add(&entry, RDF_TYPE, Term::iri(format!("{}LexicalEntry", ONTOLEX)));
+            graph.add(&entry, format!("{}canonicalForm", ONTOLEX), form.clone());
+            graph.add(&form, format!("{}writtenRep", ONTOLEX), Term::lang_literal(sense.lemma.clone(), &sense.language));
+            graph.add(&entry, format!("{}partOfSpeech", WN), Term::iri(format!("{}{}", WN, self.pos.as_long_string())));
+            graph.add(&entry, format!("{}sense", ONTOLEX), sense_node.clone());
+            for subcat in sense.subcats.iter() {
+                let behaviour = graph.blank();
+                graph.add(&entry, format!("{}synBehavior", SYNSEM), behaviour.clone());
+                graph.add(&behaviour, format!("{}label", RDFS), Term::lang_literal(subcat.clone(), "en"));
+            }
+            graph.add(&sense_node, RDF_TYPE, Term::iri(format!("{}LexicalSense", ONTOLEX)));
+            graph.add(&sense_node, format!("{}isLexicalizedSenseOf", ONTOLEX), synset.clone());
+            for relation in self.relations.iter() {
+                if let (Some(src), Some(trg)) = (&relation.src_word, &relation.trg_word) {
+                    if src != &sense.lemma {
+                        continue;
+                    }
+                    let target = Term::iri(sense_iri(trg, &relation.target));
+                    graph.add(&sense_node, format!("{}{}", WN, relation.rel_type), target.clone());
+                    let reified = graph.blank();
+                    graph.add(&reified, RDF_TYPE, Term::iri(format!("{}SenseRelation", VARTRANS)));
+                    graph.add(&reified, format!("{}source", VARTRANS), sense_node.clone());
+                    graph.add(&reified, format!("{}category", VARTRANS), Term::iri(format!("{}{}", WN, relation.rel_type)));
+                    graph.add(&reified, format!("{}target", VARTRANS), target);
+                }
+            }
+        }
+        for (language, lemma) in self.translations.iter() {
+            let entry = Term::iri(translation_entry_iri(language, lemma, pos));
+            let form = Term::iri(format!("{}-form", translation_entry_iri(language, lemma, pos)));
+            let sense_node = Term::iri(format!("{}-{}", translation_entry_iri(language, lemma, pos), iri_segment(self.id.as_str())));
+            graph.add(&entry, RDF_TYPE, Term::iri(format!("{}LexicalEntry", ONTOLEX)));
+            graph.add(&entry, format!("{}canonicalForm", ONTOLEX), form.clone());
+            graph.add(&form, format!("{}writtenRep", ONTOLEX), Term::lang_literal(lemma.clone(), language));
+            graph.add(&entry, format!("{}partOfSpeech", WN), Term::iri(format!("{}{}", WN, self.pos.as_long_string())));
+            graph.add(&entry, format!("{}sense", ONTOLEX), sense_node.clone());
+            graph.add(&sense_node, RDF_TYPE, Term::iri(format!("{}LexicalSense", ONTOLEX)));
+            graph.add(&sense_node, format!("{}isLexicalizedSenseOf", ONTOLEX), synset.clone());
+        }
+        graph.add(&synset, RDF_TYPE, Term::iri(format!("{}LexicalConcept", ONTOLEX)));
+        if let Some(ili) = &self.ili {
+            graph.add(&synset, format!("{}ili", WN), Term::iri(format!("{}{}", ILI, iri_segment(ili.as_str()))));
+        }
+        if let Some(qid) = &self.wikidata {
+            let item = Term::iri(format!("{}{}", WIKIDATA, iri_segment(qid.trim())));
+            graph.add(&synset, format!("{}sameAs", OWL), item.clone());
+            graph.add(&synset, format!("{}exactMatch", SKOS), item);
+        }
+        graph.add(&synset, format!("{}partOfSpeech", WN), Term::iri(format!("{}{}", WN, self.pos.as_long_string())));
+        graph.add(&synset, format!("{}subject", DC), Term::literal(self.subject.clone()));
+        let definition = graph.blank();
+        graph.add(&synset, format!("{}definition", WN), definition.clone());
+        graph.add(&definition, format!("{}value", crate::rdf::RDF), Term::lang_literal(self.definition.clone(), "en"));
+        for example in self.examples.iter() {
+            let node = graph.blank();
+            graph.add(&synset, format!("{}example", WN), node.clone());
+            graph.add(&node, format!("{}value", crate::rdf::RDF), Term::lang_literal(example.clone(), "en"));
+        }
+        graph.add(&synset, format!("{}inScheme", SKOS), Term::iri(format!("{}/", SITE_URL)));
+        for relation in self.relations.iter().filter(|r| r.src_word.is_none()) {
+            let target = Term::iri(synset_iri(&relation.target));
+            graph.add(&synset, format!("{}{}", WN, relation.rel_type), target.clone());
+            let reified = graph.blank();
+            graph.add(&reified, RDF_TYPE, Term::iri(format!("{}ConceptRelation", VARTRANS)));
+            graph.add(&reified, format!("{}source", VARTRANS), synset.clone());
+            graph.add(&reified, format!("{}category", VARTRANS), Term::iri(format!("{}{}", WN, relation.rel_type)));
+            graph.add(&reified, format!("{}target", VARTRANS), target);
         }
     }
 }
@@ -149,6 +317,7 @@ pub struct Sense {
     pub sense_key : Option<String>,
     pub subcats : Vec<String>,
     pub pronunciations : Vec<Pronunciation>,
+    pub count : u32,
 }
 
 impl Sense {
@@ -161,6 +330,7 @@ impl Sense {
             sense_key: Some(entry.sense.id.to_string()),
             subcats: entry.sense.subcat.clone(),
             pronunciations: entry.pronunciation.clone(),
+            count: entry.count,
         }
     }
 }
@@ -174,19 +344,26 @@ pub struct Relation {
 }
 
 pub fn make_synsets_hb(synset_data : Vec<MemberSynset>, 
-                    index : &str, name : &str) -> SynsetsHB {
+                    index : &str, name : &str, translations : &Translations) -> SynsetsHB {
     let mut entries = HashMap::new();
     let mut synsets = Vec::new();
     for synset in synset_data.iter() {
+        let s2 = HBSynset::from(synset).with_translations(translations);
         for lemma in synset.members.iter() {
-            let s2 = HBSynset::from(synset);
             entries.entry("en".to_string())
                 .or_insert_with(|| HashMap::new())
                 .entry(format!("{}-{}", lemma.lemma, synset.part_of_speech.str()))
                 .or_insert_with(|| Vec::new())
                 .push(s2.clone());
-            synsets.push(s2);
         }
+        for (language, lemma) in s2.translations.iter() {
+            entries.entry(language.clone())
+                .or_insert_with(|| HashMap::new())
+                .entry(format!("{}-{}", lemma, synset.part_of_speech.str()))
+                .or_insert_with(|| Vec::new())
+                .push(s2.clone());
+        }
+        synsets.push(s2);
     }
     SynsetsHB {
         synsets,
@@ -197,32 +374,3 @@ pub fn make_synsets_hb(synset_data : Vec<MemberSynset>,
         site: SITE_URL
     }
 }
-
-pub fn lemma_escape(h : &Helper,
-                _ : &Handlebars,
-                _ : &Context,
-                _ : &mut RenderContext,
-                out : &mut dyn Output) -> HelperResult {
-    let param = h.param(0).and_then(|v| v.value().as_str()).unwrap_or("");
-    out.write(&param.replace(" ", "_"))?;
-    Ok(())
-}
-
-pub fn long_pos(h : &Helper,
-                _ : &Handlebars,
-                _ : &Context,
-                _ : &mut RenderContext,
-                out : &mut dyn Output) -> HelperResult {
-    let param = h.param(0)
-        .ok_or_else(|| handlebars::RenderErrorReason::ParamNotFoundForName("pos_long", "No parameter for pos_long".to_string()))
-        .and_then(|v| {
-            let v2 = v.value().as_str()
-                .ok_or_else(|| handlebars::RenderErrorReason::ParamTypeMismatchForName("pos_long", "String".to_string(), "something else".to_string()))?;
-            crate::wordnet::PartOfSpeech::from_str(v2)
-                .map_err(|e| handlebars::RenderErrorReason::Other(format!("{}", e)))
-        })?;
-    out.write(&param.as_long_string())?;
-    Ok(())
-}
-
-
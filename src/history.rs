@@ -0,0 +1,220 @@
+/// The history of a synset across the editions of the wordnet
+///
+
+use crate::diff::{SynsetDiff, diff_synset};
+use crate::wordnet::{Lexicon, MemberSynset, SynsetId};
+use serde::Serialize;
+
+/// What happened to a synset in an edition, compared to the edition before
+#[derive(Serialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Event {
+    /// The synset is in the oldest edition
+    Present,
+    /// The synset is not in this edition nor the one before: it has not
+    /// been added yet, or it was removed, merged or split earlier
+    Absent,
+    Added,
+    Unchanged,
+    Changed,
+    /// The synset has a new ID, found through its ILI
+    Renamed,
+    /// The senses of the synset are now in several synsets
+    Split,
+    /// The senses of the synset are now in another existing synset
+    Merged,
+    Removed,
+}
+
+#[derive(Serialize)]
+pub struct HistoryEntry {
+    pub edition : String,
+    pub event : Event,
+    /// The ID of the synset in this edition
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id : Option<SynsetId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source : Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changes : Option<SynsetDiff>,
+    /// The synsets that now contain the senses of a removed synset
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub successors : Vec<SynsetId>,
+}
+
+/// Follow a synset through the editions, from the oldest to the newest.
+/// Returns `None` if no edition contains the synset.
+pub fn history(editions : &[(&str, &Lexicon)], id : &SynsetId) -> Option<Vec<HistoryEntry>> {
+    let mut timeline = Vec::new();
+    let mut previous : Option<MemberSynset> = None;
+    let mut last_id = id.clone();
+    let mut last_ili : Option<String> = None;
+    for (i, (edition, wn)) in editions.iter().enumerate() {
+        let synset = wn.synset_by_id(&last_id)
+            .or_else(|| wn.synset_by_id(id))
+            .or_else(|| last_ili.as_ref()
+                .and_then(|ili| wn.synset_id_by_ili(ili))
+                .and_then(|ssid| wn.synset_by_id(ssid)));
+        let mut entry = HistoryEntry {
+            edition: edition.to_string(),
+            event: Event::Absent,
+            id: synset.as_ref().map(|s| s.id.clone()),
+            source: synset.as_ref().and_then(|s| s.source.clone()),
+            changes: None,
+            successors: Vec::new(),
+        };
+        match (synset.as_ref(), previous.as_ref()) {
+            (Some(_), None) => {
+                entry.event = if i == 0 { Event::Present } else { Event::Added };
+            },
+            (Some(synset), Some(old)) => {
+                entry.changes = diff_synset(old, synset);
+                entry.event = if old.id != synset.id {
+                    Event::Renamed
+                } else if entry.changes.is_some() {
+                    Event::Changed
+                } else {
+                    Event::Unchanged
+                };
+            },
+            (None, Some(old)) => {
+                entry.successors = successors(wn, old);
+                entry.event = match entry.successors.len() {
+                    0 => Event::Removed,
+                    1 => Event::Merged,
+                    _ => Event::Split
+                };
+            },
+            (None, None) => ()
+        }
+        if let Some(synset) = synset.as_ref() {
+            last_id = synset.id.clone();
+            if let Some(ili) = synset.ili.as_ref().filter(|i| !i.is_new()) {
+                last_ili = Some(ili.as_str().to_string());
+            }
+        }
+        previous = synset;
+        timeline.push(entry);
+    }
+    if timeline.iter().all(|e| e.id.is_none()) {
+        None
+    } else {
+        Some(timeline)
+    }
+}
+
+/// The synsets of an edition that contain a sense of the given synset
+fn successors(wn : &Lexicon, synset : &MemberSynset) -> Vec<SynsetId> {
+    let mut successors = Vec::new();
+    for member in synset.members.iter() {
+        for ssid in wn.entry_by_lemma(&member.lemma) {
+            if successors.contains(&ssid) {
+                continue;
+            }
+            if let Some(candidate) = wn.synset_by_id(&ssid) {
+                if candidate.members.iter().any(|m| m.sense.id == member.sense.id) {
+                    successors.push(ssid);
+                }
+            }
+        }
+    }
+    successors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wordnet::tests::{TAXONOMY, lexicon_from_lmf};
+
+    const FIRST_SYNSET : &str = r#"    <Synset id="oewn-00000001-n""#;
+
+    /// Replace a part of the test taxonomy, which must be there
+    fn edit(xml : &str, from : &str, to : &str) -> String {
+        assert!(xml.contains(from), "{}", from);
+        xml.replace(from, to)
+    }
+
+    fn events(timeline : &[HistoryEntry]) -> Vec<&Event> {
+        timeline.iter().map(|e| &e.event).collect()
+    }
+
+    #[test]
+    fn renamed() {
+        let old = lexicon_from_lmf("history-renamed-old", TAXONOMY);
+        let xml = edit(&TAXONOMY.replace("oewn-00000004-n", "oewn-00000012-n"), "a feline mammal", "a small feline mammal");
+        let new = lexicon_from_lmf("history-renamed-new", &xml);
+        let timeline = history(&[("1", &old), ("2", &new), ("3", &new)], &SynsetId::new("00000004-n")).unwrap();
+        assert_eq!(events(&timeline), vec![&Event::Present, &Event::Renamed, &Event::Unchanged]);
+        assert_eq!(timeline[1].id, Some(SynsetId::new("00000012-n")));
+        assert!(timeline[1].changes.as_ref().unwrap().definition.is_some());
+        assert_eq!(timeline[2].id, Some(SynsetId::new("00000012-n")));
+    }
+
+    #[test]
+    fn split() {
+        let xml = edit(TAXONOMY, FIRST_SYNSET, &format!(r#"    <LexicalEntry id="oewn-moggy-n">
+      <Lemma writtenForm="moggy" partOfSpeech="n"/>
+      <Sense id="oewn-moggy__1.05.00.." dc:identifier="moggy%1:05:00::" synset="oewn-00000004-n"/>
+    </LexicalEntry>
+{}"#, FIRST_SYNSET));
+        let old = lexicon_from_lmf("history-split-old", &xml);
+        // The cat and the moggy go to new synsets, neither of which has the ILI
+        let xml = edit(&xml, r#"dc:identifier="cat%1:05:00::" synset="oewn-00000004-n""#,
+            r#"dc:identifier="cat%1:05:00::" synset="oewn-00000012-n""#);
+        let xml = edit(&xml, r#"dc:identifier="moggy%1:05:00::" synset="oewn-00000004-n""#,
+            r#"dc:identifier="moggy%1:05:00::" synset="oewn-00000013-n""#);
+        let xml = edit(&xml, r#"<Synset id="oewn-00000004-n" ili="i46593""#, r#"<Synset id="oewn-00000012-n" ili="""#);
+        let xml = edit(&xml, FIRST_SYNSET, &format!(r#"    <Synset id="oewn-00000013-n" ili="" partOfSpeech="n" lexfile="noun.animal">
+      <Definition>a house cat</Definition>
+    </Synset>
+{}"#, FIRST_SYNSET));
+        let new = lexicon_from_lmf("history-split-new", &xml);
+        let timeline = history(&[("1", &old), ("2", &new)], &SynsetId::new("00000004-n")).unwrap();
+        assert_eq!(events(&timeline), vec![&Event::Present, &Event::Split]);
+        assert_eq!(timeline[1].id, None);
+        assert_eq!(timeline[1].successors, vec![SynsetId::new("00000012-n"), SynsetId::new("00000013-n")]);
+    }
+
+    #[test]
+    fn merged() {
+        let old = lexicon_from_lmf("history-merged-old", TAXONOMY);
+        // The puppy becomes a sense of the dog synset
+        let xml = edit(TAXONOMY, r#"synset="oewn-00000005-n""#, r#"synset="oewn-00000003-n""#);
+        let xml = edit(&xml, r#"    <Synset id="oewn-00000005-n" ili="" partOfSpeech="n" lexfile="noun.animal">
+      <Definition>a young dog</Definition>
+      <SynsetRelation relType="hypernym" target="oewn-00000003-n"/>
+    </Synset>
+"#, "");
+        let new = lexicon_from_lmf("history-merged-new", &xml);
+        let timeline = history(&[("1", &old), ("2", &new)], &SynsetId::new("00000005-n")).unwrap();
+        assert_eq!(events(&timeline), vec![&Event::Present, &Event::Merged]);
+        assert_eq!(timeline[1].successors, vec![SynsetId::new("00000003-n")]);
+        let dog = history(&[("1", &old), ("2", &new)], &SynsetId::new("00000003-n")).unwrap();
+        assert_eq!(events(&dog), vec![&Event::Present, &Event::Changed]);
+        assert_eq!(dog[1].changes.as_ref().unwrap().added_members.len(), 1);
+    }
+
+    #[test]
+    fn removed() {
+        let old = lexicon_from_lmf("history-removed-old", TAXONOMY);
+        let xml = edit(TAXONOMY, r#"    <LexicalEntry id="oewn-jog-v">
+      <Lemma writtenForm="jog" partOfSpeech="v"/>
+      <Sense id="oewn-jog__2.38.00.." dc:identifier="jog%2:38:00::" synset="oewn-00000007-v" subcat="oewn-frame-via"/>
+    </LexicalEntry>
+"#, "");
+        let xml = edit(&xml, r#"    <Synset id="oewn-00000007-v" ili="i32090" partOfSpeech="v" lexfile="verb.motion">
+      <Definition>run slowly</Definition>
+      <SynsetRelation relType="hypernym" target="oewn-00000006-v"/>
+    </Synset>
+"#, "");
+        let new = lexicon_from_lmf("history-removed-new", &xml);
+        let editions = [("1", &old), ("2", &new), ("3", &new)];
+        let timeline = history(&editions, &SynsetId::new("00000007-v")).unwrap();
+        assert_eq!(events(&timeline), vec![&Event::Present, &Event::Removed, &Event::Absent]);
+        assert!(timeline[1].successors.is_empty());
+        // Added in a later edition, so absent from the earlier ones
+        let added = history(&[("2", &new), ("1", &old)], &SynsetId::new("00000007-v")).unwrap();
+        assert_eq!(events(&added), vec![&Event::Absent, &Event::Added]);
+        assert!(history(&editions, &SynsetId::new("00000099-v")).is_none());
+    }
+}
@@ -0,0 +1,171 @@
+/// Information content from sense-annotated corpora
+///
+
+use crate::wordnet::{Lexicon, SynsetId};
+use std::collections::{HashMap, HashSet};
+
+/// The similarity measures based on information content
+pub enum IcMeasure { Resnik, JiangConrath, Lin }
+
+impl IcMeasure {
+    pub fn from_str(s : &str) -> Option<IcMeasure> {
+        match s {
+            "res" => Some(IcMeasure::Resnik),
+            "jcn" => Some(IcMeasure::JiangConrath),
+            "lin" => Some(IcMeasure::Lin),
+            _ => None
+        }
+    }
+}
+
+/// The Jiang-Conrath similarity of synsets at no distance, which NLTK uses
+/// in place of infinity (which JSON cannot represent)
+pub const JCN_IDENTICAL : f64 = 1e300;
+
+/// The frequency of each noun and verb synset, including the frequencies
+/// of all the synsets below it, with add-one smoothing
+pub struct InformationContent {
+    freq : HashMap<SynsetId, f64>,
+    totals : HashMap<char, f64>,
+}
+
+impl InformationContent {
+    /// Propagate the number of times each synset was annotated up the
+    /// hypernym hierarchy
+    pub fn new(lexicon : &Lexicon, counts : &HashMap<SynsetId, usize>) -> InformationContent {
+        let mut hypernyms = HashMap::new();
+        for ssid in lexicon.synset_ids.iter() {
+            if ic_pos(ssid).is_some() {
+                hypernyms.insert(ssid.clone(), lexicon.hypernyms(ssid));
+            }
+        }
+        let mut freq = HashMap::new();
+        let mut totals = HashMap::new();
+        for (ssid, pos) in hypernyms.keys().filter_map(|s| ic_pos(s).map(|p| (s, p))) {
+            let count = counts.get(ssid).copied().unwrap_or(0) as f64 + 1.0;
+            *totals.entry(pos).or_insert(0.0) += count;
+            let mut visited = HashSet::new();
+            let mut stack = vec![ssid];
+            while let Some(s) = stack.pop() {
+                if visited.insert(s) {
+                    *freq.entry(s.clone()).or_insert(0.0) += count;
+                    stack.extend(hypernyms.get(s).into_iter().flatten());
+                }
+            }
+        }
+        InformationContent { freq, totals }
+    }
+
+    /// The information content of a synset, `-log p(s)`
+    pub fn ic(&self, synset_id : &SynsetId) -> Option<f64> {
+        let total = self.totals.get(&ic_pos(synset_id)?)?;
+        self.freq.get(synset_id).map(|f| -(f / total).ln())
+    }
+
+    /// Compute a similarity measure between two synsets
+    pub fn similarity(&self, lexicon : &Lexicon, measure : &IcMeasure, a : &SynsetId, b : &SynsetId) -> Option<f64> {
+        match measure {
+            IcMeasure::Resnik => self.resnik(lexicon, a, b),
+            IcMeasure::JiangConrath => self.jcn(lexicon, a, b),
+            IcMeasure::Lin => self.lin(lexicon, a, b),
+        }
+    }
+
+    /// Resnik similarity, the information content of the most informative
+    /// common subsumer
+    pub fn resnik(&self, lexicon : &Lexicon, a : &SynsetId, b : &SynsetId) -> Option<f64> {
+        if ic_pos(a)? != ic_pos(b)? {
+            return None;
+        }
+        let ancestors_a : HashSet<SynsetId> = lexicon.ancestors(a).into_iter()
+            .map(|(s, _)| s).chain(std::iter::once(a.clone())).collect();
+        lexicon.ancestors(b).into_iter()
+            .map(|(s, _)| s).chain(std::iter::once(b.clone()))
+            .filter(|s| ancestors_a.contains(s))
+            .filter_map(|s| self.ic(&s))
+            .fold(None, |best : Option<f64>, ic| Some(best.map_or(ic, |b| b.max(ic))))
+            // Verbs without a common subsumer share the (assumed) root
+            .or(Some(0.0))
+    }
+
+    /// Jiang-Conrath similarity, `1 / (IC(a) + IC(b) - 2 IC(lcs))`. As in
+    /// NLTK, this is 0 if either synset is a root and `JCN_IDENTICAL` if
+    /// the distance is 0, as it is for identical synsets
+    pub fn jcn(&self, lexicon : &Lexicon, a : &SynsetId, b : &SynsetId) -> Option<f64> {
+        let lcs = self.resnik(lexicon, a, b)?;
+        let (ic_a, ic_b) = (self.ic(a)?, self.ic(b)?);
+        if ic_a == 0.0 || ic_b == 0.0 {
+            return Some(0.0);
+        }
+        let distance = ic_a + ic_b - 2.0 * lcs;
+        if distance <= 0.0 {
+            Some(JCN_IDENTICAL)
+        } else {
+            Some(1.0 / distance)
+        }
+    }
+
+    /// Lin similarity, `2 IC(lcs) / (IC(a) + IC(b))`
+    pub fn lin(&self, lexicon : &Lexicon, a : &SynsetId, b : &SynsetId) -> Option<f64> {
+        let lcs = self.resnik(lexicon, a, b)?;
+        let sum = self.ic(a)? + self.ic(b)?;
+        if sum == 0.0 {
+            None
+        } else {
+            Some(2.0 * lcs / sum)
+        }
+    }
+}
+
+/// Information content is only defined for nouns and verbs
+fn ic_pos(synset_id : &SynsetId) -> Option<char> {
+    match synset_id.as_str().chars().last() {
+        Some('n') => Some('n'),
+        Some('v') => Some('v'),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wordnet::tests::{TAXONOMY, lexicon_from_lmf};
+
+    fn id(s : &str) -> SynsetId {
+        SynsetId::new(s)
+    }
+
+    fn assert_close(value : Option<f64>, expected : f64) {
+        let value = value.unwrap();
+        assert!((value - expected).abs() < 1e-6, "{} != {}", value, expected);
+    }
+
+    // With dog annotated 3 times and cat once, the smoothed frequencies
+    // are puppy 1, dog 5, cat 2, animal 8 and entity 9 of 9 noun
+    // annotations, so IC(dog) = -ln(5/9) and IC(animal) = -ln(8/9)
+    #[test]
+    fn ic_similarities() {
+        let (entity, animal, dog, cat, puppy, jog, eat) = (id("00000001-n"), id("00000002-n"),
+            id("00000003-n"), id("00000004-n"), id("00000005-n"), id("00000007-v"), id("00000008-v"));
+        let wn = lexicon_from_lmf("ic", TAXONOMY);
+        let counts = HashMap::from([(dog.clone(), 3), (cat.clone(), 1)]);
+        let ic = InformationContent::new(&wn, &counts);
+        assert_close(ic.ic(&entity), 0.0);
+        assert_close(ic.ic(&animal), 0.117783);
+        assert_close(ic.ic(&dog), 0.587787);
+
+        assert_close(ic.resnik(&wn, &dog, &cat), 0.117783);
+        assert_close(ic.resnik(&wn, &puppy, &dog), 0.587787);
+        assert_close(ic.resnik(&wn, &jog, &eat), 0.0);
+        assert_eq!(ic.resnik(&wn, &dog, &jog), None);
+
+        assert_close(ic.jcn(&wn, &dog, &cat), 0.538707);
+        assert_close(ic.jcn(&wn, &puppy, &dog), 0.621335);
+        assert_eq!(ic.jcn(&wn, &dog, &dog), Some(JCN_IDENTICAL));
+        assert_eq!(ic.jcn(&wn, &entity, &dog), Some(0.0));
+
+        assert_close(ic.lin(&wn, &dog, &cat), 0.112611);
+        assert_close(ic.lin(&wn, &puppy, &dog), 0.422107);
+        assert_close(ic.lin(&wn, &dog, &dog), 1.0);
+    }
+}
@@ -0,0 +1,1008 @@
+/// Import and export in the Global WordNet Association's WN-LMF XML format
+///
+
+use crate::omw::{TranslationSource, Translations};
+use crate::rdf::escape_xml;
+use crate::wordnet::{Entries, Entry, Example, Lexicon, Member, MemberSynset, PosKey,
+    Pronunciation, Sense, SenseRelation, Synset, SynsetId, Synsets, entry_key};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::reader::Reader;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{self, BufRead, Write};
+use thiserror::Error;
+
+const DOCTYPE : &str = "<!DOCTYPE LexicalResource SYSTEM \"http://globalwordnet.github.io/schemas/WN-LMF-1.3.dtd\">";
+const DC : &str = "https://globalwordnet.github.io/schemas/dc/";
+const LICENSE : &str = "https://creativecommons.org/licenses/by/4.0/";
+const EMAIL : &str = "english-wordnet@googlegroups.com";
+const URL : &str = "https://github.com/globalwordnet/english-wordnet";
+const ID_PREFIX : &str = "oewn";
+
+/// Writes a WN-LMF document: the lexical entries must all be written
+/// before the synsets
+pub struct LmfWriter<'a, W : Write> {
+    out : W,
+    lexicon : &'a Lexicon,
+}
+
+impl<'a, W : Write> LmfWriter<'a, W> {
+    /// Start a document with a single lexicon of the given version
+    pub fn new(mut out : W, lexicon : &'a Lexicon, version : &str) -> io::Result<LmfWriter<'a, W>> {
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(out, "{}", DOCTYPE)?;
+        writeln!(out, "<LexicalResource xmlns:dc=\"{}\">", DC)?;
+        writeln!(out, "  <Lexicon id=\"{}\" label=\"Open English WordNet\" language=\"en\" email=\"{}\" license=\"{}\" version=\"{}\" url=\"{}\">",
+            ID_PREFIX, EMAIL, LICENSE, escape_xml(version, true), URL)?;
+        Ok(LmfWriter { out, lexicon })
+    }
+
+    /// Write the lexical entries of a lemma, with a sense for each of the
+    /// given synsets that the lemma is a member of
+    pub fn write_entries(&mut self, lemma : &str, synsets : &[MemberSynset]) -> io::Result<()> {
+        let mut by_entry : BTreeMap<String, Vec<(&MemberSynset, &Member)>> = BTreeMap::new();
+        for synset in synsets.iter() {
+            for member in synset.members.iter().filter(|m| m.lemma == lemma) {
+                let poskey = member.poskey.as_ref().map(|p| p.as_str().to_string())
+                    .unwrap_or_else(|| synset.part_of_speech.str().to_string());
+                by_entry.entry(poskey).or_insert_with(Vec::new).push((synset, member));
+            }
+        }
+        for (poskey, senses) in by_entry.iter() {
+            let (_, first) = senses[0];
+            let pos = poskey.chars().next().unwrap_or('n');
+            writeln!(self.out, "    <LexicalEntry id=\"{}\">", entry_id(lemma, poskey))?;
+            if first.pronunciation.is_empty() {
+                writeln!(self.out, "      <Lemma writtenForm=\"{}\" partOfSpeech=\"{}\"/>",
+                    escape_xml(lemma, true), pos)?;
+            } else {
+                writeln!(self.out, "      <Lemma writtenForm=\"{}\" partOfSpeech=\"{}\">",
+                    escape_xml(lemma, true), pos)?;
+                for pronunciation in first.pronunciation.iter() {
+                    match &pronunciation.variety {
+                        Some(variety) => writeln!(self.out, "        <Pronunciation variety=\"{}\">{}</Pronunciation>",
+                            escape_xml(variety, true), escape_xml(&pronunciation.value, false))?,
+                        None => writeln!(self.out, "        <Pronunciation>{}</Pronunciation>",
+                            escape_xml(&pronunciation.value, false))?
+                    }
+                }
+                writeln!(self.out, "      </Lemma>")?;
+            }
+            for form in first.form.iter() {
+                writeln!(self.out, "      <Form writtenForm=\"{}\"/>", escape_xml(form, true))?;
+            }
+            for (synset, member) in senses.iter() {
+                self.write_sense(synset, member)?;
+            }
+            writeln!(self.out, "    </LexicalEntry>")?;
+        }
+        Ok(())
+    }
+
+    fn write_sense(&mut self, synset : &MemberSynset, member : &Member) -> io::Result<()> {
+        let relations = self.sense_relations(synset, &member.lemma);
+        let subcat = if member.sense.subcat.is_empty() {
+            String::new()
+        } else {
+            format!(" subcat=\"{}\"", member.sense.subcat.iter()
+                .map(|f| frame_id(f))
+                .collect::<Vec<String>>()
+                .join(" "))
+        };
+        let open = format!("      <Sense id=\"{}\" synset=\"{}\"{}", sense_id(member.sense.id.as_str()),
+            synset_id(&synset.id), subcat);
+        if relations.is_empty() && member.count == 0 {
+            return writeln!(self.out, "{}/>", open);
+        }
+        writeln!(self.out, "{}>", open)?;
+        for (rel_type, target) in relations {
+            writeln!(self.out, "        <SenseRelation relType=\"{}\" target=\"{}\"/>", rel_type, target)?;
+        }
+        if member.count > 0 {
+            writeln!(self.out, "        <Count>{}</Count>", member.count)?;
+        }
+        writeln!(self.out, "      </Sense>")
+    }
+
+    /// The sense relations from a lemma in a synset, with the target sense
+    /// IDs looked up in the target synsets
+    fn sense_relations(&self, synset : &MemberSynset, lemma : &str) -> Vec<(&'static str, String)> {
+        let mut relations = Vec::new();
+        let mut add = |rel_type : &'static str, rels : &[SenseRelation]| {
+            for r in rels.iter().filter(|r| r.source_lemma == lemma) {
+                let target = self.lexicon.synset_by_id(&r.target_synset).and_then(|t| t.members.iter()
+                    .find(|m| m.lemma == r.target_lemma)
+                    .map(|m| sense_id(m.sense.id.as_str())));
+                if let Some(target) = target {
+                    relations.push((rel_type, target));
+                }
+            }
+        };
+        // Only the relation types in the DTD are written; the inverses of
+        // the others are implied. Those given on both senses in the sources,
+        // such as `also`, are written as they are
+        add("antonym", &synset.antonym);
+        add("also", &synset.also_sense);
+        add("participle", &synset.participle);
+        add("pertainym", &synset.pertainym);
+        add("derivation", &synset.derivation);
+        add("domain_topic", &synset.domain_topic_sense);
+        add("has_domain_topic", &synset.has_domain_topic_sense);
+        add("domain_region", &synset.domain_region_sense);
+        add("has_domain_region", &synset.has_domain_region_sense);
+        add("exemplifies", &synset.exemplifies_sense);
+        add("is_exemplified_by", &synset.is_exemplified_by_sense);
+        add("similar", &synset.similar_sense);
+        add("other", &synset.other);
+        add("agent", &synset.agent);
+        add("material", &synset.material);
+        add("event", &synset.event);
+        add("instrument", &synset.instrument);
+        add("location", &synset.location);
+        add("by_means_of", &synset.by_means_of);
+        add("undergoer", &synset.undergoer);
+        add("property", &synset.property);
+        add("result", &synset.result);
+        add("state", &synset.state);
+        add("uses", &synset.uses);
+        add("destination", &synset.destination);
+        add("body_part", &synset.body_part);
+        add("vehicle", &synset.vehicle);
+        relations
+    }
+
+    pub fn write_synset(&mut self, synset : &MemberSynset) -> io::Result<()> {
+        let ili = synset.ili.as_ref().map(|i| i.as_str()).unwrap_or("");
+        let members = synset.members.iter()
+            .map(|m| sense_id(m.sense.id.as_str()))
+            .collect::<Vec<String>>()
+            .join(" ");
+        let source = synset.source.as_ref()
+            .map(|s| format!(" dc:source=\"{}\"", escape_xml(s, true)))
+            .unwrap_or_default();
+        writeln!(self.out, "    <Synset id=\"{}\" ili=\"{}\" partOfSpeech=\"{}\" members=\"{}\" lexfile=\"{}\"{}>",
+            synset_id(&synset.id), escape_xml(ili, true), synset.part_of_speech.str(), members,
+            escape_xml(&synset.lexname, true), source)?;
+        for definition in synset.definition.iter() {
+            writeln!(self.out, "      <Definition>{}</Definition>", escape_xml(definition, false))?;
+        }
+        if ili == "in" {
+            if let Some(definition) = synset.definition.first() {
+                writeln!(self.out, "      <ILIDefinition>{}</ILIDefinition>", escape_xml(definition, false))?;
+            }
+        }
+        for example in synset.example.iter() {
+            match &example.source {
+                Some(source) => writeln!(self.out, "      <Example dc:source=\"{}\">{}</Example>",
+                    escape_xml(source, true), escape_xml(&example.text, false))?,
+                None => writeln!(self.out, "      <Example>{}</Example>", escape_xml(&example.text, false))?
+            }
+        }
+        macro_rules! add_rel {
+            ($rel:ident) => {
+                for target in synset.$rel.iter() {
+                    writeln!(self.out, "      <SynsetRelation relType=\"{}\" target=\"{}\"/>",
+                        stringify!($rel), synset_id(target))?;
+                }
+            }
+        }
+        add_rel!(also);
+        add_rel!(attribute);
+        add_rel!(causes);
+        add_rel!(domain_region);
+        add_rel!(domain_topic);
+        add_rel!(exemplifies);
+        add_rel!(entails);
+        add_rel!(hypernym);
+        add_rel!(instance_hypernym);
+        add_rel!(mero_member);
+        add_rel!(mero_part);
+        add_rel!(mero_substance);
+        add_rel!(similar);
+        add_rel!(hyponym);
+        add_rel!(is_caused_by);
+        add_rel!(has_domain_region);
+        add_rel!(has_domain_topic);
+        add_rel!(is_exemplified_by);
+        add_rel!(is_entailed_by);
+        add_rel!(instance_hyponym);
+        add_rel!(holo_member);
+        add_rel!(holo_part);
+        add_rel!(holo_substance);
+        writeln!(self.out, "    </Synset>")
+    }
+
+    /// Write the subcategorization frames and close the lexicon
+    pub fn end_lexicon(&mut self, frames : &[&String]) -> io::Result<()> {
+        for frame in frames.iter() {
+            if let Some(text) = self.lexicon.frame(frame) {
+                writeln!(self.out, "    <SyntacticBehaviour id=\"{}\" subcategorizationFrame=\"{}\"/>",
+                    frame_id(frame), escape_xml(text, true))?;
+            }
+        }
+        writeln!(self.out, "  </Lexicon>")
+    }
+
+    /// Write a lexicon of another language, whose synsets are linked to the
+    /// given OEWN synsets by their ILI
+    pub fn write_translations(&mut self, language : &str, source : &TranslationSource,
+        lemmas : &[(&String, &MemberSynset)]) -> io::Result<()> {
+        let prefix = format!("omw-{}", escape_id(language));
+        writeln!(self.out, "  <Lexicon id=\"{}\" label=\"{}\" language=\"{}\" email=\"{}\" license=\"{}\" version=\"{}\">",
+            prefix, escape_xml(&source.label, true), escape_xml(language, true), escape_xml(&source.email, true),
+            escape_xml(&source.license, true), escape_xml(&source.version, true))?;
+        // A synset without an ILI cannot be linked, so neither can its senses
+        let lemmas : Vec<(&String, &MemberSynset)> = lemmas.iter()
+            .filter(|(_, synset)| synset.ili.is_some())
+            .copied()
+            .collect();
+        let mut by_entry : BTreeMap<(&str, &str), Vec<&MemberSynset>> = BTreeMap::new();
+        for (lemma, synset) in lemmas.iter() {
+            by_entry.entry((lemma.as_str(), synset.part_of_speech.str())).or_insert_with(Vec::new).push(synset);
+        }
+        for ((lemma, pos), synsets) in by_entry.iter() {
+            writeln!(self.out, "    <LexicalEntry id=\"{}-{}-{}\">", prefix, escape_id(lemma), pos)?;
+            writeln!(self.out, "      <Lemma writtenForm=\"{}\" partOfSpeech=\"{}\"/>", escape_xml(lemma, true), pos)?;
+            for synset in synsets.iter() {
+                writeln!(self.out, "      <Sense id=\"{}-{}-{}\" synset=\"{}-{}\"/>",
+                    prefix, escape_id(lemma), escape_id(synset.id.as_str()), prefix, escape_id(synset.id.as_str()))?;
+            }
+            writeln!(self.out, "    </LexicalEntry>")?;
+        }
+        let mut written = HashSet::new();
+        for (_, synset) in lemmas.iter() {
+            if !written.insert(&synset.id) {
+                continue;
+            }
+            if let Some(ili) = synset.ili.as_ref() {
+                writeln!(self.out, "    <Synset id=\"{}-{}\" ili=\"{}\" partOfSpeech=\"{}\"/>",
+                    prefix, escape_id(synset.id.as_str()), escape_xml(ili.as_str(), true), synset.part_of_speech.str())?;
+            }
+        }
+        writeln!(self.out, "  </Lexicon>")
+    }
+
+    /// Close the document
+    pub fn finish(&mut self) -> io::Result<()> {
+        writeln!(self.out, "</LexicalResource>")?;
+        self.out.flush()
+    }
+}
+
+/// Write a document containing the given synsets and the entries of their
+/// members, followed by the lemmas of other languages for the same ILIs
+pub fn write_synsets<W : Write>(out : W, lexicon : &Lexicon, version : &str,
+    synsets : &[MemberSynset], translations : &Translations) -> io::Result<()> {
+    let mut writer = LmfWriter::new(out, lexicon, version)?;
+    let mut lemmas : Vec<&String> = synsets.iter()
+        .flat_map(|s| s.members.iter().map(|m| &m.lemma))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    lemmas.sort();
+    for lemma in lemmas {
+        writer.write_entries(lemma, synsets)?;
+    }
+    let mut frames = HashSet::new();
+    for synset in synsets.iter() {
+        writer.write_synset(synset)?;
+        frames.extend(synset.members.iter().flat_map(|m| m.sense.subcat.iter()));
+    }
+    let mut frames : Vec<&String> = frames.into_iter().collect();
+    frames.sort();
+    writer.end_lexicon(&frames)?;
+    let mut by_language : BTreeMap<&String, Vec<(&String, &MemberSynset)>> = BTreeMap::new();
+    for synset in synsets.iter() {
+        if let Some(by_ili) = synset.ili.as_ref().and_then(|ili| translations.by_ili(ili.as_str())) {
+            for (language, lemmas) in by_ili.iter() {
+                by_language.entry(language).or_insert_with(Vec::new)
+                    .extend(lemmas.iter().map(|l| (l, synset)));
+            }
+        }
+    }
+    for (language, lemmas) in by_language.iter() {
+        let source = translations.source(language).cloned().unwrap_or_default();
+        writer.write_translations(language, &source, lemmas)?;
+    }
+    writer.finish()
+}
+
+/// Write the whole lexicon, one lemma and then one synset at a time
+pub fn write_lexicon<W : Write>(out : W, lexicon : &Lexicon, version : &str) -> io::Result<()> {
+    let mut writer = LmfWriter::new(out, lexicon, version)?;
+    for lemma in lexicon.lemmas() {
+        let synsets : Vec<MemberSynset> = lexicon.entry_by_lemma(lemma).iter()
+            .filter_map(|id| lexicon.synset_by_id(id))
+            .collect();
+        writer.write_entries(lemma, &synsets)?;
+    }
+    for id in lexicon.synset_ids.iter() {
+        if let Some(synset) = lexicon.synset_by_id(id) {
+            writer.write_synset(&synset)?;
+        }
+    }
+    writer.end_lexicon(&lexicon.frame_ids())?;
+    writer.finish()
+}
+
+/// Escape a string so that it can be part of an XML ID. Hyphens are
+/// doubled so that the escapes cannot be confused with the text
+fn escape_id(s : &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '-' => escaped.push_str("--"),
+            ' ' => escaped.push('_'),
+            '%' => escaped.push_str("__"),
+            ':' => escaped.push('.'),
+            '\'' => escaped.push_str("-ap-"),
+            '/' => escaped.push_str("-sl-"),
+            '!' => escaped.push_str("-ex-"),
+            ',' => escaped.push_str("-cm-"),
+            '+' => escaped.push_str("-pl-"),
+            '(' => escaped.push_str("-lb-"),
+            ')' => escaped.push_str("-rb-"),
+            c if c.is_alphanumeric() || c == '_' || c == '.' => escaped.push(c),
+            c => escaped.push_str(&format!("-{:04X}-", c as u32))
+        }
+    }
+    escaped
+}
+
+fn entry_id(lemma : &str, poskey : &str) -> String {
+    format!("{}-{}-{}", ID_PREFIX, escape_id(lemma), escape_id(poskey))
+}
+
+fn sense_id(sense_key : &str) -> String {
+    format!("{}-{}", ID_PREFIX, escape_id(sense_key))
+}
+
+fn synset_id(id : &SynsetId) -> String {
+    format!("{}-{}", ID_PREFIX, escape_id(id.as_str()))
+}
+
+fn frame_id(frame : &str) -> String {
+    format!("{}-frame-{}", ID_PREFIX, escape_id(frame))
+}
+
+/// The sense key of a sense ID written by `LmfWriter`
+pub fn sense_key(id : &str) -> String {
+    let id = id.strip_prefix(ID_PREFIX).and_then(|i| i.strip_prefix('-')).unwrap_or(id);
+    sense_key_from_id(id)
+}
+
+/// Undo `escape_id` for the part of a sense ID after the lexicon prefix.
+/// The lemma is separated from the rest of the sense key by the escaped
+/// `%`, after which colons were written as full stops
+fn sense_key_from_id(id : &str) -> String {
+    match id.find("__") {
+        Some(i) => format!("{}%{}", unescape_id(&id[..i]), unescape_id(&id[i+2..]).replace('.', ":")),
+        None => unescape_id(id)
+    }
+}
+
+/// Undo the hyphen escapes of `escape_id`
+fn unescape_id(s : &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('-') {
+        unescaped.push_str(&rest[..i]);
+        rest = &rest[i+1..];
+        if let Some(r) = rest.strip_prefix('-') {
+            unescaped.push('-');
+            rest = r;
+            continue;
+        }
+        let code = rest.find('-').map(|j| &rest[..j]);
+        let c = match code {
+            Some("ap") => Some('\''),
+            Some("sl") => Some('/'),
+            Some("ex") => Some('!'),
+            Some("cm") => Some(','),
+            Some("pl") => Some('+'),
+            Some("lb") => Some('('),
+            Some("rb") => Some(')'),
+            Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+            None => None
+        };
+        match (c, code) {
+            (Some(c), Some(code)) => {
+                unescaped.push(c);
+                rest = &rest[code.len()+1..];
+            },
+            _ => unescaped.push('-')
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// The synsets, entries and subcategorization frames of a WN-LMF document,
+/// grouped in the same way as the YAML source files
+pub struct LmfData {
+    pub synsets : HashMap<String, Synsets>,
+    pub entries : HashMap<String, Entries>,
+    pub frames : HashMap<String, String>,
+}
+
+/// Read a WN-LMF document (any version from 1.0 to 1.3) containing a
+/// single lexicon. A document with no lexicon, several lexicons or a lexicon
+/// extension is rejected, rather than loading only a part of it
+pub fn read_lmf<R : BufRead>(input : R) -> Result<LmfData, LmfError> {
+    let mut reader = Reader::from_reader(input);
+    let mut parser = LmfParser::default();
+    let mut buf = Vec::new();
+    loop {
+        let event = reader.read_event_into(&mut buf)
+            .map_err(|e| LmfError::Xml(format!("at position {}: {}", reader.buffer_position(), e)))?;
+        match event {
+            Event::Start(e) => parser.start(&e)?,
+            Event::Empty(e) => {
+                parser.start(&e)?;
+                parser.end(e.name().as_ref())?;
+            },
+            Event::End(e) => parser.end(e.name().as_ref())?,
+            Event::Text(e) => if let Some(text) = parser.text.as_mut() {
+                text.push_str(&e.unescape().map_err(|e| LmfError::Xml(e.to_string()))?);
+            },
+            Event::CData(e) => if let Some(text) = parser.text.as_mut() {
+                text.push_str(&String::from_utf8_lossy(&e));
+            },
+            Event::Eof => break,
+            _ => ()
+        }
+        buf.clear();
+    }
+    if parser.prefix.is_empty() {
+        return Err(LmfError::Invalid("The document contains no lexicon".to_string()));
+    }
+    parser.finish()
+}
+
+#[derive(Default)]
+struct LmfEntry {
+    lemma : String,
+    pos : String,
+    forms : Vec<String>,
+    pronunciations : Vec<Pronunciation>,
+    senses : Vec<LmfSense>,
+}
+
+#[derive(Default)]
+struct LmfSense {
+    id : String,
+    key : Option<String>,
+    synset : String,
+    adjposition : Option<String>,
+    subcat : Vec<String>,
+    relations : Vec<(String, String)>,
+    examples : Vec<String>,
+}
+
+#[derive(Default)]
+struct LmfSynset {
+    id : String,
+    ili : String,
+    pos : String,
+    members : Vec<String>,
+    lexfile : Option<String>,
+    source : Option<String>,
+    definitions : Vec<String>,
+    examples : Vec<Example>,
+    relations : Vec<(String, String)>,
+}
+
+/// The state of reading a WN-LMF document
+#[derive(Default)]
+struct LmfParser {
+    prefix : String,
+    entries : Vec<LmfEntry>,
+    synsets : Vec<LmfSynset>,
+    /// Frames by their ID (WN-LMF 1.1 and later)
+    frames : Vec<(String, String)>,
+    /// Frames given inside an entry with the IDs of their senses
+    /// (WN-LMF 1.0)
+    sense_frames : Vec<(String, Vec<String>)>,
+    entry : Option<LmfEntry>,
+    synset : Option<LmfSynset>,
+    /// The content of the text element being read
+    text : Option<String>,
+    text_attrs : HashMap<String, String>,
+}
+
+/// Synset relations that are the inverse of another relation, which
+/// `Lexicon` adds itself
+const INVERSE_RELATIONS : &[&str] = &["hyponym", "instance_hyponym", "holo_member",
+    "holo_part", "holo_substance", "is_caused_by", "is_exemplified_by", "is_entailed_by"];
+
+impl LmfParser {
+    fn start(&mut self, e : &BytesStart) -> Result<(), LmfError> {
+        let mut attrs = HashMap::new();
+        for attr in e.attributes() {
+            let attr = attr.map_err(|e| LmfError::Xml(e.to_string()))?;
+            let value = attr.unescape_value().map_err(|e| LmfError::Xml(e.to_string()))?;
+            attrs.insert(String::from_utf8_lossy(attr.key.as_ref()).to_string(), value.to_string());
+        }
+        let split = |attrs : &HashMap<String, String>, key : &str| attrs.get(key)
+            .map(|v| v.split_whitespace().map(|s| s.to_string()).collect())
+            .unwrap_or_else(Vec::new);
+        match e.name().as_ref() {
+            b"Lexicon" => {
+                let id = required(&attrs, "id", "Lexicon")?;
+                if let Some(first) = self.prefix.strip_suffix('-') {
+                    return Err(LmfError::Invalid(format!(
+                        "The document contains more than one lexicon ({} and {}), but only one can be loaded", first, id)));
+                }
+                self.prefix = format!("{}-", id);
+            },
+            b"LexiconExtension" => {
+                return Err(LmfError::Invalid(format!(
+                    "Lexicon extensions such as {} cannot be loaded", attrs.get("id").map(|x| x.as_str()).unwrap_or(""))));
+            },
+            b"LexicalEntry" => self.entry = Some(LmfEntry::default()),
+            b"Lemma" => if let Some(entry) = self.entry.as_mut() {
+                entry.lemma = required(&attrs, "writtenForm", "Lemma")?;
+                entry.pos = required(&attrs, "partOfSpeech", "Lemma")?;
+            },
+            b"Form" => if let Some(entry) = self.entry.as_mut() {
+                entry.forms.push(required(&attrs, "writtenForm", "Form")?);
+            },
+            b"Sense" => if let Some(entry) = self.entry.as_mut() {
+                entry.senses.push(LmfSense {
+                    id: required(&attrs, "id", "Sense")?,
+                    key: attrs.get("dc:identifier").cloned(),
+                    synset: required(&attrs, "synset", "Sense")?,
+                    adjposition: attrs.get("adjposition").cloned(),
+                    subcat: split(&attrs, "subcat"),
+                    ..LmfSense::default()
+                });
+            },
+            b"SenseRelation" => if let Some(sense) = self.entry.as_mut().and_then(|e| e.senses.last_mut()) {
+                sense.relations.push((required(&attrs, "relType", "SenseRelation")?,
+                    required(&attrs, "target", "SenseRelation")?));
+            },
+            b"SyntacticBehaviour" => {
+                let frame = required(&attrs, "subcategorizationFrame", "SyntacticBehaviour")?;
+                if let Some(id) = attrs.get("id") {
+                    self.frames.push((id.clone(), frame.clone()));
+                }
+                if let Some(entry) = self.entry.as_ref() {
+                    let senses = if attrs.contains_key("senses") {
+                        split(&attrs, "senses")
+                    } else {
+                        entry.senses.iter().map(|s| s.id.clone()).collect()
+                    };
+                    self.sense_frames.push((attrs.get("id").cloned().unwrap_or(frame), senses));
+                }
+            },
+            b"Synset" => self.synset = Some(LmfSynset {
+                id: required(&attrs, "id", "Synset")?,
+                ili: attrs.get("ili").cloned().unwrap_or_default(),
+                pos: attrs.get("partOfSpeech").cloned().unwrap_or_default(),
+                members: split(&attrs, "members"),
+                lexfile: attrs.get("lexfile").cloned(),
+                source: attrs.get("dc:source").cloned(),
+                ..LmfSynset::default()
+            }),
+            b"SynsetRelation" => if let Some(synset) = self.synset.as_mut() {
+                synset.relations.push((required(&attrs, "relType", "SynsetRelation")?,
+                    required(&attrs, "target", "SynsetRelation")?));
+            },
+            b"Pronunciation" | b"Definition" | b"ILIDefinition" | b"Example" => {
+                self.text = Some(String::new());
+                self.text_attrs = attrs;
+            },
+            _ => ()
+        }
+        Ok(())
+    }
+
+    fn end(&mut self, name : &[u8]) -> Result<(), LmfError> {
+        match name {
+            b"LexicalEntry" => if let Some(entry) = self.entry.take() {
+                if entry.lemma.is_empty() {
+                    return Err(LmfError::Invalid("LexicalEntry without a Lemma".to_string()));
+                }
+                self.entries.push(entry);
+            },
+            b"Synset" => self.synsets.extend(self.synset.take()),
+            b"Pronunciation" => if let Some(entry) = self.entry.as_mut() {
+                entry.pronunciations.push(Pronunciation {
+                    value: self.text.take().unwrap_or_default(),
+                    variety: self.text_attrs.remove("variety")
+                });
+            },
+            b"Definition" => if let Some(synset) = self.synset.as_mut() {
+                synset.definitions.push(self.text.take().unwrap_or_default());
+            },
+            b"Example" => {
+                let text = self.text.take().unwrap_or_default();
+                if let Some(synset) = self.synset.as_mut() {
+                    synset.examples.push(Example { text, source: self.text_attrs.remove("dc:source") });
+                } else if let Some(sense) = self.entry.as_mut().and_then(|e| e.senses.last_mut()) {
+                    sense.examples.push(text);
+                }
+            },
+            b"ILIDefinition" => self.text = None,
+            _ => ()
+        }
+        Ok(())
+    }
+
+    /// Remove the lexicon prefix from an ID
+    fn strip<'a>(&self, id : &'a str) -> &'a str {
+        id.strip_prefix(&self.prefix).unwrap_or(id)
+    }
+
+    /// Convert the entries and synsets to the structures of the YAML files
+    fn finish(self) -> Result<LmfData, LmfError> {
+        let frame_prefix = format!("{}frame-", self.prefix);
+        let frame_key = |id : &str| id.strip_prefix(&frame_prefix)
+            .map(unescape_id)
+            .unwrap_or_else(|| self.strip(id).to_string());
+        let mut frames = HashMap::new();
+        for (id, frame) in self.frames.iter() {
+            frames.insert(frame_key(id), frame.clone());
+        }
+        // WN-LMF 1.0 frames have no ID, so the text is used instead
+        let mut frames_by_sense : HashMap<&str, Vec<String>> = HashMap::new();
+        for (frame, senses) in self.sense_frames.iter() {
+            let key = if frames.contains_key(&frame_key(frame)) {
+                frame_key(frame)
+            } else {
+                let key = frames.iter().find(|(_, f)| *f == frame).map(|(k, _)| k.clone())
+                    .unwrap_or_else(|| format!("frame-{}", frames.len() + 1));
+                frames.insert(key.clone(), frame.clone());
+                key
+            };
+            for sense in senses.iter() {
+                frames_by_sense.entry(sense.as_str()).or_insert_with(Vec::new).push(key.clone());
+            }
+        }
+
+        let mut sense_keys = HashMap::new();
+        let mut sense_lemmas = HashMap::new();
+        let mut synset_senses : HashMap<&str, Vec<&str>> = HashMap::new();
+        for entry in self.entries.iter() {
+            for sense in entry.senses.iter() {
+                sense_keys.insert(sense.id.as_str(), sense.key.clone()
+                    .unwrap_or_else(|| sense_key_from_id(self.strip(&sense.id))));
+                sense_lemmas.insert(sense.id.as_str(), entry.lemma.as_str());
+                synset_senses.entry(sense.synset.as_str()).or_insert_with(Vec::new).push(sense.id.as_str());
+            }
+        }
+
+        let mut pos_counts : HashMap<(&str, &str), usize> = HashMap::new();
+        for entry in self.entries.iter() {
+            *pos_counts.entry((entry.lemma.as_str(), entry.pos.as_str())).or_insert(0) += 1;
+        }
+        let mut pos_numbers : HashMap<(&str, &str), usize> = HashMap::new();
+        let mut entries : HashMap<String, Entries> = HashMap::new();
+        for entry in self.entries.iter() {
+            let key = (entry.lemma.as_str(), entry.pos.as_str());
+            let n = pos_numbers.entry(key).or_insert(0);
+            *n += 1;
+            let poskey = if pos_counts[&key] > 1 {
+                PosKey::new(&format!("{}-{}", entry.pos, n))
+            } else {
+                PosKey::new(&entry.pos)
+            };
+            let mut senses = Vec::new();
+            for sense in entry.senses.iter() {
+                let mut value = Map::new();
+                value.insert("id".to_string(), Value::from(sense_keys[sense.id.as_str()].clone()));
+                value.insert("synset".to_string(), Value::from(self.strip(&sense.synset)));
+                if let Some(adjposition) = sense.adjposition.as_ref() {
+                    value.insert("adjposition".to_string(), Value::from(adjposition.as_str()));
+                }
+                let mut subcat : Vec<String> = Vec::new();
+                for frame in sense.subcat.iter().map(|s| frame_key(s))
+                    .chain(frames_by_sense.get(sense.id.as_str()).into_iter().flatten().cloned()) {
+                    if !subcat.contains(&frame) {
+                        subcat.push(frame);
+                    }
+                }
+                if !subcat.is_empty() {
+                    value.insert("subcat".to_string(), Value::from(subcat));
+                }
+                for (rel_type, target) in sense.relations.iter() {
+                    let target = sense_keys.get(target.as_str()).cloned()
+                        .unwrap_or_else(|| self.strip(target).to_string());
+                    if let Some(targets) = value.entry(rel_type.clone())
+                        .or_insert_with(|| Value::Array(Vec::new())).as_array_mut() {
+                        targets.push(Value::from(target));
+                    }
+                }
+                if !sense.examples.is_empty() {
+                    value.insert("sent".to_string(), Value::from(sense.examples.clone()));
+                }
+                let sense : Sense = serde_json::from_value(Value::Object(value))
+                    .map_err(|e| LmfError::Invalid(format!("Sense {}: {}", sense.id, e)))?;
+                senses.push(sense);
+            }
+            entries.entry(entry_key(&entry.lemma)).or_insert_with(|| Entries(BTreeMap::new()))
+                .0.entry(entry.lemma.clone()).or_insert_with(BTreeMap::new)
+                .insert(poskey.clone(), Entry {
+                    sense: senses,
+                    form: entry.forms.clone(),
+                    pronunciation: entry.pronunciations.clone(),
+                    poskey: Some(poskey)
+                });
+        }
+
+        let mut synsets : HashMap<String, Synsets> = HashMap::new();
+        for synset in self.synsets.iter() {
+            let id = SynsetId::new(self.strip(&synset.id));
+            let members : Vec<&str> = if synset.members.is_empty() {
+                synset_senses.get(synset.id.as_str()).cloned().unwrap_or_default()
+            } else {
+                synset.members.iter().map(|m| m.as_str()).collect()
+            };
+            let members : Vec<&str> = members.iter()
+                .map(|m| sense_lemmas.get(m).copied()
+                    .ok_or_else(|| LmfError::Invalid(format!("Synset {} has unknown member {}", synset.id, m))))
+                .collect::<Result<_, _>>()?;
+            let mut value = Map::new();
+            value.insert("definition".to_string(), Value::from(synset.definitions.clone()));
+            value.insert("members".to_string(), Value::from(members));
+            value.insert("partOfSpeech".to_string(), Value::from(synset.pos.as_str()));
+            if !synset.ili.is_empty() {
+                value.insert("ili".to_string(), Value::from(synset.ili.as_str()));
+            }
+            if let Some(source) = synset.source.as_ref() {
+                value.insert("source".to_string(), Value::from(source.as_str()));
+            }
+            if !synset.examples.is_empty() {
+                value.insert("example".to_string(), serde_json::to_value(&synset.examples)
+                    .map_err(|e| LmfError::Invalid(e.to_string()))?);
+            }
+            for (rel_type, target) in synset.relations.iter() {
+                if INVERSE_RELATIONS.contains(&rel_type.as_str()) {
+                    continue;
+                }
+                if let Some(targets) = value.entry(rel_type.clone())
+                    .or_insert_with(|| Value::Array(Vec::new())).as_array_mut() {
+                    targets.push(Value::from(self.strip(target)));
+                }
+            }
+            let mut synset2 : Synset = serde_json::from_value(Value::Object(value))
+                .map_err(|e| LmfError::Invalid(format!("Synset {}: {}", synset.id, e)))?;
+            let lexname = synset.lexfile.clone()
+                .unwrap_or_else(|| format!("lmf.{}", synset.pos));
+            synset2.id = Some(id.clone());
+            synset2.lexname = Some(lexname.clone());
+            synsets.entry(lexname).or_insert_with(|| Synsets(BTreeMap::new()))
+                .0.insert(id, synset2);
+        }
+        Ok(LmfData { synsets, entries, frames })
+    }
+}
+
+/// The lemmas of a lexicon, with the ILI of the synset of each sense
+pub struct LexiconLemmas {
+    pub language : String,
+    pub label : String,
+    pub email : String,
+    pub license : String,
+    pub version : String,
+    pub lemmas : Vec<(String, String)>,
+}
+
+/// Read only the lemmas and the ILIs of their synsets from a WN-LMF
+/// document, which may contain several lexicons
+pub fn read_lmf_lemmas<R : BufRead>(input : R) -> Result<Vec<LexiconLemmas>, LmfError> {
+    let mut reader = Reader::from_reader(input);
+    let mut buf = Vec::new();
+    let mut lexicons = Vec::new();
+    let mut senses : Vec<(String, String)> = Vec::new();
+    let mut ilis : HashMap<String, String> = HashMap::new();
+    let mut lemma = String::new();
+    loop {
+        let event = reader.read_event_into(&mut buf)
+            .map_err(|e| LmfError::Xml(format!("at position {}: {}", reader.buffer_position(), e)))?;
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let attr = |key : &str| -> Result<String, LmfError> {
+                    match e.try_get_attribute(key).map_err(|e| LmfError::Xml(e.to_string()))? {
+                        Some(a) => Ok(a.unescape_value().map_err(|e| LmfError::Xml(e.to_string()))?.to_string()),
+                        None => Ok(String::new())
+                    }
+                };
+                match e.name().as_ref() {
+                    b"Lexicon" => lexicons.push(LexiconLemmas {
+                        language: attr("language")?,
+                        label: attr("label")?,
+                        email: attr("email")?,
+                        license: attr("license")?,
+                        version: attr("version")?,
+                        lemmas: Vec::new(),
+                    }),
+                    b"Lemma" => lemma = attr("writtenForm")?,
+                    b"Sense" => senses.push((attr("synset")?, lemma.clone())),
+                    b"Synset" => {
+                        let ili = attr("ili")?;
+                        if !ili.is_empty() && ili != "in" {
+                            ilis.insert(attr("id")?, ili);
+                        }
+                    },
+                    _ => ()
+                }
+            },
+            Event::End(ref e) if e.name().as_ref() == b"Lexicon" => {
+                let lexicon = lexicons.last_mut()
+                    .ok_or_else(|| LmfError::Invalid("Unexpected end of lexicon".to_string()))?;
+                for (synset, lemma) in senses.drain(..) {
+                    if let Some(ili) = ilis.get(&synset) {
+                        lexicon.lemmas.push((ili.clone(), lemma));
+                    }
+                }
+                ilis.clear();
+            },
+            Event::Eof => break,
+            _ => ()
+        }
+        buf.clear();
+    }
+    Ok(lexicons)
+}
+
+fn required(attrs : &HashMap<String, String>, key : &str, element : &str) -> Result<String, LmfError> {
+    attrs.get(key).cloned()
+        .ok_or_else(|| LmfError::Invalid(format!("{} is missing the {} attribute", element, key)))
+}
+
+#[derive(Error, Debug)]
+pub enum LmfError {
+    #[error("Could not read WN-LMF: {0}")]
+    Io(String),
+    #[error("Invalid XML: {0}")]
+    Xml(String),
+    #[error("Invalid WN-LMF: {0}")]
+    Invalid(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wordnet::SenseId;
+    use crate::wordnet::tests::{TAXONOMY, lexicon_from_lmf};
+    use std::fs;
+    use std::path::Path;
+    use std::process::Command;
+
+    /// Check a document against the WN-LMF 1.3 DTD with xmllint. The tests
+    /// that call this are ignored by default, as they need xmllint
+    fn validate(name : &str, xml : &[u8]) {
+        let dir = std::env::temp_dir().join(format!("oewn-test-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("out.xml");
+        fs::write(&file, xml).unwrap();
+        let dtd = Path::new(env!("CARGO_MANIFEST_DIR")).join("schemas/WN-LMF-1.3.dtd");
+        let output = Command::new("xmllint").arg("--noout").arg("--nonet")
+            .arg("--dtdvalid").arg(&dtd).arg(&file).output()
+            .unwrap_or_else(|e| panic!("Could not run xmllint to validate {}: {}", name, e));
+        assert!(output.status.success(), "{}\n{}",
+            String::from_utf8_lossy(&output.stderr), String::from_utf8_lossy(xml));
+    }
+
+    #[test]
+    #[ignore = "needs xmllint"]
+    fn lexicon_is_valid() {
+        let wn = lexicon_from_lmf("lmf-lexicon", TAXONOMY);
+        let mut out = Vec::new();
+        write_lexicon(&mut out, &wn, "test").unwrap();
+        validate("lmf-lexicon", &out);
+    }
+
+    /// The test taxonomy followed by a French lexicon for the dog and the
+    /// puppy, which has no ILI
+    fn translations_document(name : &str) -> Vec<u8> {
+        let wn = lexicon_from_lmf(name, TAXONOMY);
+        let synsets : Vec<MemberSynset> = wn.synset_ids.iter()
+            .filter_map(|id| wn.synset_by_id(id))
+            .collect();
+        let dog = synsets.iter().find(|s| s.id.as_str() == "00000003-n").unwrap();
+        let puppy = synsets.iter().find(|s| s.id.as_str() == "00000005-n").unwrap();
+        let (chien, chiot) = ("chien".to_string(), "chiot".to_string());
+        let source = TranslationSource { label: "Test".to_string(), ..TranslationSource::default() };
+        let mut out = Vec::new();
+        let mut writer = LmfWriter::new(&mut out, &wn, "test").unwrap();
+        for lemma in wn.lemmas() {
+            writer.write_entries(lemma, &synsets).unwrap();
+        }
+        for synset in synsets.iter() {
+            writer.write_synset(synset).unwrap();
+        }
+        writer.end_lexicon(&wn.frame_ids()).unwrap();
+        writer.write_translations("fr", &source, &[(&chien, dog), (&chiot, puppy)]).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+        out
+    }
+
+    #[test]
+    fn translations() {
+        let out = translations_document("lmf-translations");
+        let xml = String::from_utf8_lossy(&out);
+        assert!(xml.contains("<Lemma writtenForm=\"chien\""));
+        assert!(!xml.contains("chiot"));
+    }
+
+    #[test]
+    #[ignore = "needs xmllint"]
+    fn translations_are_valid() {
+        validate("lmf-translations-valid", &translations_document("lmf-translations-valid"));
+    }
+
+    #[test]
+    fn read_written_lexicon() {
+        let wn = lexicon_from_lmf("lmf-read", TAXONOMY);
+        let mut out = Vec::new();
+        write_lexicon(&mut out, &wn, "test").unwrap();
+        let data = read_lmf(out.as_slice()).unwrap();
+        let dog = &data.synsets["noun.animal"].0[&SynsetId::new("00000003-n")];
+        assert_eq!(dog.definition, vec!["a \"domestic\" <canine> & pet,\nin Welsh «ci»".to_string()]);
+        assert_eq!(dog.example[0].text, "the dog barked\ntwice");
+    }
+
+    /// The test taxonomy with a sense relation of each type that has no
+    /// inverse in `MemberSynset`
+    fn sense_relations_document(name : &str) -> Vec<u8> {
+        let xml = TAXONOMY
+            .replace(r#"synset="oewn-00000003-n"/>"#, r#"synset="oewn-00000003-n">
+        <SenseRelation relType="also" target="oewn-cat__1.05.00.."/>
+        <SenseRelation relType="has_domain_topic" target="oewn-run__2.38.00.."/>
+        <SenseRelation relType="other" target="oewn-puppy__1.05.00.."/>
+      </Sense>"#)
+            .replace(r#"synset="oewn-00000004-n"/>"#, r#"synset="oewn-00000004-n">
+        <SenseRelation relType="similar" target="oewn-dog__1.05.00.."/>
+        <SenseRelation relType="domain_region" target="oewn-entity__1.03.00.."/>
+      </Sense>"#)
+            .replace(r#"synset="oewn-00000006-v" subcat="oewn-frame-via"/>"#, r#"synset="oewn-00000006-v" subcat="oewn-frame-via">
+        <SenseRelation relType="domain_topic" target="oewn-dog__1.05.00.."/>
+        <SenseRelation relType="has_domain_region" target="oewn-eat__2.34.00.."/>
+      </Sense>"#);
+        let wn = lexicon_from_lmf(name, &xml);
+        let mut out = Vec::new();
+        write_lexicon(&mut out, &wn, "test").unwrap();
+        out
+    }
+
+    #[test]
+    fn sense_relations() {
+        let out = sense_relations_document("lmf-sense-relations");
+        let written = String::from_utf8_lossy(&out);
+        for (rel_type, target) in [("also", "cat"), ("has_domain_topic", "run"), ("other", "puppy"),
+            ("similar", "dog"), ("domain_region", "entity"), ("domain_topic", "dog"), ("has_domain_region", "eat")] {
+            assert_eq!(written.matches(&format!("<SenseRelation relType=\"{}\" target=\"oewn-{}__", rel_type, target)).count(), 1,
+                "{} {}\n{}", rel_type, target, written);
+        }
+        let data = read_lmf(out.as_slice()).unwrap();
+        let dog = &data.entries["d"].0["dog"].values().next().unwrap().sense[0];
+        assert_eq!(dog.also, vec![SenseId::new("cat%1:05:00::")]);
+        assert_eq!(dog.other, vec![SenseId::new("puppy%1:05:00::")]);
+    }
+
+    #[test]
+    #[ignore = "needs xmllint"]
+    fn sense_relations_are_valid() {
+        validate("lmf-sense-relations-valid", &sense_relations_document("lmf-sense-relations-valid"));
+    }
+
+    #[test]
+    fn several_lexicons() {
+        let lexicon = |id : &str| format!("<Lexicon id=\"{}\" label=\"Test\" language=\"en\" email=\"test@example.com\" license=\"https://creativecommons.org/licenses/by/4.0/\" version=\"test\">\n\
+            <LexicalEntry id=\"{}-dog-n\"><Lemma writtenForm=\"dog\" partOfSpeech=\"n\"/><Sense id=\"{}-dog-n-1\" synset=\"{}-00000003-n\"/></LexicalEntry>\n\
+            <Synset id=\"{}-00000003-n\" ili=\"i46360\" partOfSpeech=\"n\"/>\n</Lexicon>", id, id, id, id, id);
+        let xml = format!("<LexicalResource>\n{}\n{}\n</LexicalResource>", lexicon("oewn"), lexicon("other"));
+        match read_lmf(xml.as_bytes()) {
+            Err(LmfError::Invalid(msg)) => assert!(msg.contains("oewn and other"), "{}", msg),
+            Err(e) => panic!("Unexpected error {}", e),
+            Ok(_) => panic!("Read only one of two lexicons")
+        }
+        let lexicons = read_lmf_lemmas(xml.as_bytes()).unwrap();
+        assert_eq!(lexicons.len(), 2);
+        assert_eq!(lexicons[1].lemmas, vec![("i46360".to_string(), "dog".to_string())]);
+        assert!(matches!(read_lmf("<LexicalResource/>".as_bytes()), Err(LmfError::Invalid(_))));
+    }
+}
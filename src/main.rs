@@ -1,22 +1,44 @@
 #[macro_use] extern crate rocket;
 
+mod changes;
+mod cili;
+mod diff;
 mod hbs;
+mod history;
+mod ic;
+mod lmf;
 mod negotiation;
+mod omw;
+mod pwn;
+mod rdf;
+mod search;
+mod similarity;
+mod sparql;
 mod wordnet;
 
 use clap::Parser;
+use flate2::Compression;
+use flate2::write::GzEncoder;
 use handlebars::Handlebars;
-use negotiation::{ContentNegotiation, NegotiatedResponse, negotiated};
+use negotiation::{ContentNegotiation, NegotiatedResponse, negotiated, split_format};
+use rdf::RdfWriter;
 use rocket::config::Config as RocketConfig;
+use rocket::form::Form;
 use rocket::fs::FileServer;
 use rocket::response::content::{RawHtml, RawJson};
 use rocket::response::Redirect;
+use rocket::response::status;
+use rocket::serde::json::Json;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Request, Outcome};
 use rocket::http::ContentType;
 use once_cell::sync::OnceCell;
 use wordnet::{Lexicon, SynsetId, MemberSynset};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::path::Path;
+use std::sync::Mutex;
 use serde::Serialize;
 use teanga::Corpus;
 use teanga::disk_corpus::{DiskCorpus, RedbDb};
@@ -33,33 +55,152 @@ struct Config {
     port: u16,
     #[arg(long, help = "Reload the wordnet from the given folder")]
     wn: Option<String>,
-    #[arg(long, help = "Dump all the RDF Turtle data to this file (Server does not start)")]
-    dump_ttl : Option<String>,
+    #[arg(long, value_name = "TOKEN", help = "Accept changes from the editor that carry this bearer token, saving them to --wn")]
+    edit_token: Option<String>,
+    #[arg(long, help = "Load the wordnet from a WN-LMF XML file (optionally gzipped) instead of the YAML sources")]
+    wn_lmf: Option<String>,
+    #[arg(long, default_value = "english-wordnet/src/yaml", help = "The folder to rebuild the wordnet from if the cache is missing or out of date")]
+    wn_source: String,
+    #[arg(long, alias = "dump-ttl", help = "Dump all the data to this file (Server does not start)")]
+    dump : Option<String>,
+    #[arg(long, default_value = "2024", help = "The name of the edition loaded from --wn, --wn-lmf or --wn-source")]
+    edition : String,
+    #[arg(long = "other-edition", value_name = "NAME=PATH", help = "Also serve an older edition, loaded from a YAML folder or a WN-LMF file (may be repeated, oldest first)")]
+    other_editions : Vec<String>,
+    #[arg(long, help = "The edition served by the unversioned routes and dumped [default: --edition]")]
+    default_edition : Option<String>,
+    #[arg(long = "translations", value_name = "FILE", help = "A wordnet in another language, in WN-LMF or OMW tab format, to join by ILI (may be repeated)")]
+    translations : Vec<String>,
+    #[arg(long, value_name = "FILE", help = "The CILI mapping from PWN 3.0 offsets to ILIs, needed for OMW tab files")]
+    ili_map : Option<String>,
+    #[arg(long, value_name = "FILE", help = "The CILI ili.ttl, to show the definition and status of each ILI")]
+    cili : Option<String>,
+    #[arg(long, value_name = "FILE", help = "The table mapping PWN 3.0 synset offsets to OEWN, for /pwn30/<offset>")]
+    pwn30_map : Option<String>,
+    #[arg(long, value_name = "FILE", help = "The table mapping PWN 3.1 synset offsets to OEWN, for /pwn31/<offset>")]
+    pwn31_map : Option<String>,
+    #[arg(long, value_enum, default_value = "ttl", help = "The format of the dump")]
+    dump_format : DumpFormat,
+    #[arg(long, help = "Compress the dump with gzip (the default if the file name ends in .gz)")]
+    gzip : bool,
+    #[command(subcommand)]
+    command : Option<Command>,
 }
 
+#[derive(clap::Subcommand,Debug)]
+enum Command {
+    /// Print the differences between two editions as JSON (Server does not start)
+    Diff {
+        #[arg(help = "The older edition, as a YAML folder or a WN-LMF file")]
+        from : String,
+        #[arg(help = "The newer edition, as a YAML folder or a WN-LMF file")]
+        to : String,
+    },
+}
+
+#[derive(clap::ValueEnum,Clone,Debug)]
+enum DumpFormat { Ttl, Nt, Rdfxml, Jsonld, LmfXml }
+
 struct State<'a> {
-    wn : wordnet::Lexicon,
+    editions : HashMap<String, wordnet::Lexicon>,
+    /// The names of the editions from oldest to newest
+    edition_order : Vec<String>,
     handlebars : Handlebars<'a>,
     corpora : HashMap<String, DiskCorpus<RedbDb>>,
+    synset_counts : HashMap<SynsetId, usize>,
+    wn_folder : Option<String>,
+    /// The edition loaded from the sources, which can be edited
+    edition : String,
+    default_edition : String,
+    edit_token : Option<String>,
+    /// Held while changes are saved, with what earlier changes did
+    edits : Mutex<changes::Edits>,
+    translations : omw::Translations,
+    cili : cili::Cili,
+    information_content : OnceCell<ic::InformationContent>,
+    sparql : OnceCell<sparql::SparqlStore>,
 }
 
 static STATE: OnceCell<State> = OnceCell::new();
 
+impl<'a> State<'a> {
+    /// The default edition
+    fn wn(&self) -> &Lexicon {
+        &self.editions[&self.default_edition]
+    }
+
+    /// An edition by its name
+    fn lexicon(&self, edition : &str) -> Option<&Lexicon> {
+        self.editions.get(edition)
+    }
+
+    /// All the editions, from oldest to newest
+    fn editions_in_order(&self) -> Vec<(&str, &Lexicon)> {
+        self.edition_order.iter()
+            .map(|name| (name.as_str(), &self.editions[name]))
+            .collect()
+    }
+}
+
 fn prepare_server(config : &Config) -> Result<(), String> {
     let mut handlebars = Handlebars::new();
-    handlebars.register_template_string("xml", include_str!("hbs/xml.hbs")).map_err(|e| format!("Failed to register template: {}", e))?;
-    handlebars.register_template_string("rdfxml", include_str!("hbs/rdfxml.hbs")).map_err(|e| format!("Failed to register template: {}", e))?;
-    handlebars.register_template_string("ttl", include_str!("hbs/ttl.hbs")).map_err(|e| format!("Failed to register template: {}", e))?;
-    handlebars.register_template_string("ttl-header", include_str!("hbs/ttl-header.hbs")).map_err(|e| format!("Failed to register template: {}", e))?;
     handlebars.register_template_string("html", include_str!("hbs/html.hbs")).map_err(|e| format!("Failed to register template: {}", e))?;
     handlebars.register_template_string("sitemap", include_str!("hbs/sitemap.hbs")).map_err(|e| format!("Failed to register template: {}", e))?;
-    handlebars.register_helper("lemma_escape", Box::new(hbs::lemma_escape));
-    handlebars.register_helper("long_pos", Box::new(hbs::long_pos));
     let wn = if let Some(ref wn_path) = config.wn {
-        Lexicon::load(wn_path)
+        Lexicon::load(wn_path, "wordnet")
             .map_err(|e| format!("Failed to load WordNet: {}", e))?
+    } else if let Some(ref lmf_file) = config.wn_lmf {
+        load_edition(Path::new(lmf_file), "wordnet")?
     } else {
-        Lexicon::from_disk()
+        let source = Path::new(&config.wn_source);
+        match Lexicon::from_disk(Some(source).filter(|p| p.exists()), "wordnet") {
+            Ok(wn) => wn,
+            Err(e) => {
+                eprintln!("{}, reloading from {}", e, config.wn_source);
+                Lexicon::load(source, "wordnet")
+                    .map_err(|e| format!("Failed to load WordNet: {}", e))?
+            }
+        }
+    };
+    let mut editions = HashMap::new();
+    let mut edition_order = Vec::new();
+    for other in config.other_editions.iter() {
+        let (name, path) = other.split_once('=')
+            .ok_or_else(|| format!("Invalid edition {}, expected NAME=PATH", other))?;
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.') {
+            return Err(format!("Invalid edition name {}", name));
+        }
+        if editions.contains_key(name) {
+            return Err(format!("Edition {} is given twice", name));
+        }
+        editions.insert(name.to_string(), load_edition(Path::new(path), &format!("wordnet-{}", name))?);
+        edition_order.push(name.to_string());
+    }
+    if editions.contains_key(&config.edition) {
+        return Err(format!("Edition {} is given twice", config.edition));
+    }
+    editions.insert(config.edition.clone(), wn);
+    edition_order.push(config.edition.clone());
+    let default_edition = config.default_edition.clone().unwrap_or_else(|| config.edition.clone());
+    let wn = editions.get_mut(&default_edition)
+        .ok_or_else(|| format!("The default edition {} is not loaded", default_edition))?;
+    for (version, file) in [(pwn::PwnVersion::Pwn30, &config.pwn30_map), (pwn::PwnVersion::Pwn31, &config.pwn31_map)] {
+        if let Some(file) = file {
+            let mapping = pwn::read_mapping(Path::new(file), wn).map_err(|e| e.to_string())?;
+            wn.set_pwn_mapping(version, mapping);
+        }
+    }
+    let ili_map = match config.ili_map {
+        Some(ref file) => omw::load_ili_map(Path::new(file)).map_err(|e| e.to_string())?,
+        None => HashMap::new()
+    };
+    let mut translations = omw::Translations::default();
+    for file in config.translations.iter() {
+        translations.load(Path::new(file), &ili_map).map_err(|e| e.to_string())?;
+    }
+    let cili = match config.cili {
+        Some(ref file) => cili::Cili::load(Path::new(file)).map_err(|e| e.to_string())?,
+        None => cili::Cili::default()
     };
     let mut corpora = HashMap::new();
     for file in vec!["raganato_ALL.yaml", "semcor.yaml"] {
@@ -77,13 +218,68 @@ fn prepare_server(config : &Config) -> Result<(), String> {
         corpus.commit().map_err(|e| format!("Failed to commit corpus: {}", e))?;
         corpora.insert(name, corpus);
     }
+    let sense_counts = corpus_counts(&corpora)?;
+    for wn in editions.values_mut() {
+        wn.set_sense_counts(&sense_counts).map_err(|e| e.to_string())?;
+    }
+    let synset_counts = sense_counts.iter()
+        .map(|(ssid, forms)| (ssid.clone(), forms.values().sum()))
+        .collect();
 
 
-    STATE.set(State { wn, handlebars, corpora }).map_err(|_| "Failed to set state".to_string())?;
+    STATE.set(State { editions, edition_order, handlebars, corpora, synset_counts,
+        wn_folder: config.wn.clone(), edition: config.edition.clone(), default_edition, edit_token: config.edit_token.clone(), edits: Mutex::new(changes::Edits::default()), translations, cili,
+        information_content: OnceCell::new(), sparql: OnceCell::new() }).map_err(|_| "Failed to set state".to_string())?;
 
     Ok(())
 }
 
+/// Open an edition from its cache, or load it from a YAML folder or a WN-LMF
+/// file if the cache is missing or out of date
+fn load_edition(source : &Path, cache : &str) -> Result<Lexicon, String> {
+    match Lexicon::from_disk(Some(source), cache) {
+        Ok(wn) => Ok(wn),
+        Err(e) => {
+            eprintln!("{}, reloading from {}", e, source.display());
+            if source.is_file() {
+                Lexicon::load_lmf(source, cache)
+                    .map_err(|e| format!("Failed to load WordNet: {}", e))
+            } else {
+                Lexicon::load(source, cache)
+                    .map_err(|e| format!("Failed to load WordNet: {}", e))
+            }
+        }
+    }
+}
+
+/// The corpora whose annotations are counted. raganato_ALL is the test data
+/// of the WSD evaluation framework, so counting it would leak the answers
+/// into the most frequent sense baseline
+const TRAINING_CORPORA : &[&str] = &["semcor"];
+
+/// Count the word forms annotated with each synset in the training corpora
+fn corpus_counts(corpora : &HashMap<String, DiskCorpus<RedbDb>>) -> Result<HashMap<SynsetId, HashMap<String, usize>>, String> {
+    let mut counts = HashMap::new();
+    for (_, corpus) in corpora.iter().filter(|(name, _)| TRAINING_CORPORA.contains(&name.as_str())) {
+        for doc_id in corpus.get_docs() {
+            let doc = corpus.get_doc_by_id(&doc_id).map_err(|e| format!("Failed to get document: {}", e))?;
+            let text = doc.text("text", corpus.get_meta())
+                .map_err(|e| format!("Failed to get text: {}", e))?
+                .iter().next().map(|x| x.to_string()).unwrap_or("".to_string());
+            for (start, end, data) in doc.indexes_data("oewn", "text", corpus.get_meta()).map_err(|e| format!("Failed to get indexes: {}", e))? {
+                if let TeangaData::String(s) = data {
+                    if let Some(id) = s.strip_prefix("oewn-") {
+                        let form : String = text.chars().skip(start).take(end - start).collect();
+                        *counts.entry(SynsetId::new(id)).or_insert_with(HashMap::new)
+                            .entry(form).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+    }
+    Ok(counts)
+}
+
 #[get("/")]
 fn index_page() -> RawHtml<&'static str> {
     RawHtml(include_str!("../dist/index.html"))
@@ -92,11 +288,17 @@ fn index_page() -> RawHtml<&'static str> {
 #[get("/lemma/<lemma>")]
 fn get_lemma(lemma: &str, neg: ContentNegotiation) -> Option<NegotiatedResponse> {
     let state = STATE.get().expect("State not set");
-    if let Some(_) = state.wn.entry_by_lemma(lemma).iter().next() {
-        Some(negotiated("lemma", lemma, include_str!("../dist/index.html"), neg))
-    } else {
-        None
-    }
+    let (lemma, neg) = split_format(lemma, neg);
+    state.wn().entry_by_form(lemma).first()?;
+    Some(negotiated("lemma", lemma, None, include_str!("../dist/index.html"), neg))
+}
+
+#[get("/<edition>/lemma/<lemma>", rank = 2)]
+fn get_edition_lemma(edition: &str, lemma: &str, neg: ContentNegotiation) -> Option<NegotiatedResponse> {
+    let state = STATE.get().expect("State not set");
+    let (lemma, neg) = split_format(lemma, neg);
+    state.lexicon(edition)?.entry_by_form(lemma).first()?;
+    Some(negotiated("lemma", lemma, Some(edition), include_str!("../dist/index.html"), neg))
 }
 
 #[get("/id/<id>")]
@@ -104,8 +306,21 @@ fn get_id(id: &str, neg: ContentNegotiation) -> Option<NegotiatedResponse> {
     let state = STATE.get().expect("State not set");
     if id.starts_with("oewn") {
         return Some(NegotiatedResponse::Redirect(Redirect::to(format!("/id/{}", &id[5..]))));
-    } else if let Some(synset) = state.wn.synset_by_id(&SynsetId::new(id)) {
-        match negotiated("id", id, include_str!("../dist/index.html"), neg) {
+    }
+    synset_page(state, state.wn(), None, id, neg)
+}
+
+#[get("/<edition>/id/<id>", rank = 2)]
+fn get_edition_id(edition: &str, id: &str, neg: ContentNegotiation) -> Option<NegotiatedResponse> {
+    let state = STATE.get().expect("State not set");
+    synset_page(state, state.lexicon(edition)?, Some(edition), id, neg)
+}
+
+/// The page of a synset, with the synset rendered into the HTML
+fn synset_page(state : &State, wn : &Lexicon, edition : Option<&str>, id : &str, neg : ContentNegotiation) -> Option<NegotiatedResponse> {
+    let (id, neg) = split_format(id, neg);
+    if let Some(synset) = wn.synset_by_id(&SynsetId::new(id)) {
+        match negotiated("id", id, edition, include_str!("../dist/index.html"), neg) {
             NegotiatedResponse::Html(RawHtml(content)) => {
                 let content = String::from(content);
                 let index_content = state.handlebars.render("html", &synset).expect("Failed to render template");
@@ -125,13 +340,102 @@ fn get_id(id: &str, neg: ContentNegotiation) -> Option<NegotiatedResponse> {
 #[get("/ili/<id>")]
 fn get_ili(id: &str, neg: ContentNegotiation) -> Option<NegotiatedResponse> {
     let state = STATE.get().expect("State not set");
-    if let Some(_) = state.wn.synset_by_ili(id) {
-        Some(negotiated("ili", id, include_str!("../dist/index.html"), neg))
-    } else {
-        None
+    let (id, neg) = split_format(id, neg);
+    state.wn().synset_by_ili(id)?;
+    Some(negotiated("ili", id, None, include_str!("../dist/index.html"), neg))
+}
+
+#[get("/<edition>/ili/<id>", rank = 2)]
+fn get_edition_ili(edition: &str, id: &str, neg: ContentNegotiation) -> Option<NegotiatedResponse> {
+    let state = STATE.get().expect("State not set");
+    let (id, neg) = split_format(id, neg);
+    state.lexicon(edition)?.synset_by_ili(id)?;
+    Some(negotiated("ili", id, Some(edition), include_str!("../dist/index.html"), neg))
+}
+
+#[get("/sense/<id>")]
+fn get_sense(id: &str, neg: ContentNegotiation) -> Option<NegotiatedResponse> {
+    let state = STATE.get().expect("State not set");
+    let (id, neg) = split_format(id, neg);
+    state.wn().synset_id_by_sense(id)?;
+    // Sense keys contain a `%`, which must be escaped in the redirect
+    Some(negotiated("sense", &id.replace('%', "%25"), None, include_str!("../dist/index.html"), neg))
+}
+
+#[get("/<edition>/sense/<id>", rank = 2)]
+fn get_edition_sense(edition: &str, id: &str, neg: ContentNegotiation) -> Option<NegotiatedResponse> {
+    let state = STATE.get().expect("State not set");
+    let (id, neg) = split_format(id, neg);
+    state.lexicon(edition)?.synset_id_by_sense(id)?;
+    Some(negotiated("sense", &id.replace('%', "%25"), Some(edition), include_str!("../dist/index.html"), neg))
+}
+
+#[derive(Responder)]
+enum PwnResponse {
+    Redirect(Redirect),
+    Candidates(status::Custom<RawJson<String>>)
+}
+
+/// The synsets that a split PWN synset became
+#[derive(Serialize)]
+struct PwnCandidates {
+    version : &'static str,
+    offset : String,
+    candidates : Vec<PwnCandidate>,
+}
+
+#[derive(Serialize)]
+struct PwnCandidate {
+    id : SynsetId,
+    lemmas : Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    definition : Option<String>,
+}
+
+#[get("/pwn30/<offset>")]
+fn get_pwn30(offset: &str) -> Option<PwnResponse> {
+    pwn_synset(pwn::PwnVersion::Pwn30, offset)
+}
+
+#[get("/pwn31/<offset>")]
+fn get_pwn31(offset: &str) -> Option<PwnResponse> {
+    pwn_synset(pwn::PwnVersion::Pwn31, offset)
+}
+
+/// Redirect a PWN synset offset to its OEWN synset, or list the synsets it
+/// was split into
+fn pwn_synset(version : pwn::PwnVersion, offset : &str) -> Option<PwnResponse> {
+    let state = STATE.get().expect("State not set");
+    let ids = state.wn().synsets_by_pwn(version, offset);
+    match ids.as_slice() {
+        [] => None,
+        [id] => Some(PwnResponse::Redirect(Redirect::to(format!("/id/{}", id.as_str())))),
+        _ => {
+            let candidates = PwnCandidates {
+                version: version.as_str(),
+                offset: offset.to_string(),
+                candidates: ids.iter()
+                    .filter_map(|id| state.wn().synset_by_id(id))
+                    .map(|synset| PwnCandidate {
+                        id: synset.id.clone(),
+                        lemmas: synset.members.iter().map(|m| m.lemma.clone()).collect(),
+                        definition: synset.definition.first().cloned()
+                    }).collect()
+            };
+            Some(PwnResponse::Candidates(status::Custom(Status::MultipleChoices,
+                RawJson(serde_json::to_string(&candidates).expect("Failed to serialize")))))
+        }
     }
 }
 
+#[get("/wikidata/<qid>")]
+fn get_wikidata(qid: &str, neg: ContentNegotiation) -> Option<NegotiatedResponse> {
+    let state = STATE.get().expect("State not set");
+    let (qid, neg) = split_format(qid, neg);
+    state.wn().synsets_by_wikidata(qid).first()?;
+    Some(negotiated("wikidata", qid, None, include_str!("../dist/index.html"), neg))
+}
+
 #[get("/downloads")]
 fn downloads() -> RawHtml<&'static str> {
     RawHtml(include_str!("../dist/index.html"))
@@ -147,6 +451,46 @@ fn edit_page2(_ssid: &str) -> RawHtml<&'static str> {
     RawHtml(include_str!("../dist/index.html"))
 }
 
+#[post("/api/changes", data = "<changes>")]
+fn post_changes(_token : EditToken, changes : Json<Vec<changes::Change>>) -> Result<RawJson<String>, status::Custom<String>> {
+    let state = STATE.get().expect("State not set");
+    let folder = state.wn_folder.as_ref()
+        .ok_or_else(|| status::Custom(Status::Conflict, "Changes can only be saved when the server is started with --wn".to_string()))?;
+    let mut edits = state.edits.lock()
+        .map_err(|_| status::Custom(Status::InternalServerError, "Failed to lock the WordNet folder".to_string()))?;
+    let files = changes::apply_changes(folder, &state.editions[&state.edition], &mut edits, &changes).map_err(|e| match e {
+        changes::ChangeError::Invalid(_) => status::Custom(Status::BadRequest, e.to_string()),
+        _ => status::Custom(Status::InternalServerError, e.to_string())
+    })?;
+    Ok(RawJson(serde_json::to_string(&files)
+        .map_err(|e| status::Custom(Status::InternalServerError, format!("Failed to serialize: {}", e)))?))
+}
+
+/// A request carrying the token given with `--edit-token`
+struct EditToken;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for EditToken {
+    type Error = String;
+    async fn from_request(request: &'r Request<'_>) -> Outcome<EditToken, String> {
+        let state = STATE.get().expect("State not set");
+        let expected = match state.edit_token.as_ref() {
+            Some(token) => token,
+            None => return Outcome::Error((Status::Forbidden, "Editing is not enabled on this server".to_string()))
+        };
+        let given = request.headers().get_one("Authorization")
+            .and_then(|h| h.strip_prefix("Bearer "))
+            .unwrap_or("");
+        // Compare every byte so that the time taken does not leak the token
+        if given.len() == expected.len() &&
+            given.bytes().zip(expected.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0 {
+            Outcome::Success(EditToken)
+        } else {
+            Outcome::Error((Status::Unauthorized, "A valid edit token is required".to_string()))
+        }
+    }
+}
+
 #[get("/favicon.ico")]
 fn favicon() -> (ContentType, &'static [u8]) {
     (ContentType::Icon, include_bytes!("favicon.ico"))
@@ -170,7 +514,7 @@ struct SiteMapData {
 #[get("/sitemap.xml")]
 fn sitemap() -> (ContentType, String) {
     let state = STATE.get().expect("State not set");
-    let synsets = state.wn.synset_ids.clone();
+    let synsets = state.wn().synset_ids.clone();
     let data = SiteMapData { synsets };
     (ContentType::new("application", "xml"),
         state.handlebars.render("sitemap", &data).expect("Failed to render template"))
@@ -179,23 +523,214 @@ fn sitemap() -> (ContentType, String) {
 #[get("/autocomplete/<index>/<query>")]
 fn autocomplete(index : &str, query: &str) -> RawJson<String> {
     let state = STATE.get().expect("State not set");
-    let mut results = if index == "lemma" {
-        state.wn.lemma_by_prefix(query)
+    let results = if index == "lemma" {
+        state.wn().lemma_by_prefix(query, 100)
     } else if index == "ili" {
-        state.wn.ili_by_prefix(query)
+        state.wn().ili_by_prefix(query, 100)
     } else if index == "id" {
-        state.wn.ssid_by_prefix(query)
+        state.wn().ssid_by_prefix(query, 100)
     } else {
         Vec::new()
     };
-    results.sort_by(|a, b| {
-        match a.to_lowercase().cmp(&b.to_lowercase()) {
-            std::cmp::Ordering::Equal => a.cmp(b).reverse(),
-            x => x
+    RawJson(serde_json::to_string(&results).expect("Failed to serialize"))
+}
+
+#[derive(Serialize)]
+struct FuzzyResult {
+    lemma: String,
+    distance: usize,
+    synsets: usize,
+}
+
+#[get("/search/fuzzy/<query>?<distance>")]
+fn fuzzy_search(query: &str, distance: Option<usize>) -> RawJson<String> {
+    let state = STATE.get().expect("State not set");
+    let distance = distance.unwrap_or(if query.chars().count() <= 4 { 1 } else { 2 }).min(3);
+    let results = state.wn().lemma_by_fuzzy(query, distance, 100).into_iter()
+        .map(|(lemma, distance)| FuzzyResult {
+            synsets: state.wn().entry_by_lemma(&lemma).len(),
+            lemma,
+            distance
+        })
+        .collect::<Vec<_>>();
+    RawJson(serde_json::to_string(&results).expect("Failed to serialize"))
+}
+
+#[derive(Serialize)]
+struct TextSearchResult {
+    id: SynsetId,
+    score: f64,
+    lemmas: Vec<String>,
+    snippet: search::Snippet,
+}
+
+#[get("/api/search?<q>&<limit>")]
+fn text_search(q: &str, limit: Option<usize>) -> Result<RawJson<String>, String> {
+    let state = STATE.get().expect("State not set");
+    let mut results = Vec::new();
+    for (id, score) in state.wn().synset_by_text(q, limit.unwrap_or(20).min(100)) {
+        if let Some(synset) = state.wn().synset_by_id(&id) {
+            results.push(TextSearchResult {
+                id,
+                score,
+                lemmas: synset.members.iter().map(|m| m.lemma.clone()).collect(),
+                snippet: search::snippet(&synset, q)
+            });
         }
+    }
+    Ok(RawJson(serde_json::to_string(&results).map_err(|e| format!("Failed to serialize: {}", e))?))
+}
+
+#[derive(Serialize)]
+struct RelatedSynset {
+    id: SynsetId,
+    depth: usize,
+}
+
+#[get("/api/synset/<id>/ancestors")]
+fn synset_ancestors(id: &str) -> Option<RawJson<String>> {
+    let state = STATE.get().expect("State not set");
+    let ssid = SynsetId::new(id);
+    state.wn().synset_by_id(&ssid)?;
+    let results = state.wn().ancestors(&ssid).into_iter()
+        .map(|(id, depth)| RelatedSynset { id, depth })
+        .collect::<Vec<_>>();
+    Some(RawJson(serde_json::to_string(&results).expect("Failed to serialize")))
+}
+
+#[get("/api/synset/<id>/descendants?<depth>")]
+fn synset_descendants(id: &str, depth: Option<usize>) -> Option<RawJson<String>> {
+    let state = STATE.get().expect("State not set");
+    let ssid = SynsetId::new(id);
+    state.wn().synset_by_id(&ssid)?;
+    let results = state.wn().descendants(&ssid, depth).into_iter()
+        .map(|(id, depth)| RelatedSynset { id, depth })
+        .collect::<Vec<_>>();
+    Some(RawJson(serde_json::to_string(&results).expect("Failed to serialize")))
+}
+
+#[get("/api/synset/<id>/paths")]
+fn synset_paths(id: &str) -> Option<RawJson<String>> {
+    let state = STATE.get().expect("State not set");
+    let ssid = SynsetId::new(id);
+    state.wn().synset_by_id(&ssid)?;
+    Some(RawJson(serde_json::to_string(state.wn().hypernym_paths(&ssid)).expect("Failed to serialize")))
+}
+
+#[get("/api/diff/<from>/<to>")]
+fn edition_diff(from: &str, to: &str) -> Option<RawJson<String>> {
+    let state = STATE.get().expect("State not set");
+    let diff = diff::diff(state.lexicon(from)?, state.lexicon(to)?);
+    Some(RawJson(serde_json::to_string(&diff).expect("Failed to serialize")))
+}
+
+#[get("/api/ili/report")]
+fn ili_report() -> RawJson<String> {
+    let state = STATE.get().expect("State not set");
+    let report = cili::report(state.wn(), &state.cili);
+    RawJson(serde_json::to_string(&report).expect("Failed to serialize"))
+}
+
+#[get("/api/history/<id>")]
+fn synset_history(id: &str) -> Option<RawJson<String>> {
+    let state = STATE.get().expect("State not set");
+    let timeline = history::history(&state.editions_in_order(), &SynsetId::new(id))?;
+    Some(RawJson(serde_json::to_string(&timeline).expect("Failed to serialize")))
+}
+
+#[derive(Serialize)]
+struct SimilarityResult {
+    measure: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lcs: Option<Vec<SynsetId>>,
+}
+
+#[get("/api/similarity/<measure>/<id1>/<id2>")]
+fn similarity(measure: &str, id1: &str, id2: &str) -> Option<RawJson<String>> {
+    let state = STATE.get().expect("State not set");
+    let (a, b) = (SynsetId::new(id1), SynsetId::new(id2));
+    state.wn().synset_by_id(&a)?;
+    state.wn().synset_by_id(&b)?;
+    let result = if measure == "lcs" {
+        SimilarityResult {
+            measure: measure.to_string(),
+            score: None,
+            lcs: Some(state.wn().lowest_common_subsumers(&a, &b))
+        }
+    } else if let Some(m) = similarity::Measure::from_str(measure) {
+        SimilarityResult {
+            measure: measure.to_string(),
+            score: state.wn().similarity(&m, &a, &b),
+            lcs: None
+        }
+    } else if let Some(m) = ic::IcMeasure::from_str(measure) {
+        let information_content = state.information_content.get_or_init(||
+            ic::InformationContent::new(state.wn(), &state.synset_counts));
+        SimilarityResult {
+            measure: measure.to_string(),
+            score: information_content.similarity(state.wn(), &m, &a, &b),
+            lcs: None
+        }
+    } else {
+        return None;
+    };
+    Some(RawJson(serde_json::to_string(&result).expect("Failed to serialize")))
+}
+
+#[derive(FromForm)]
+struct SparqlForm {
+    query : String,
+}
+
+#[get("/sparql?<query>")]
+async fn sparql_get(query : Option<&str>, accept : sparql::SparqlAccept) -> Result<(ContentType, Vec<u8>), status::Custom<String>> {
+    let query = query.ok_or_else(|| status::Custom(Status::BadRequest, "Missing query parameter".to_string()))?;
+    sparql_query(query.to_string(), accept).await
+}
+
+#[post("/sparql", format = "application/x-www-form-urlencoded", data = "<form>")]
+async fn sparql_post_form(form : Form<SparqlForm>, accept : sparql::SparqlAccept) -> Result<(ContentType, Vec<u8>), status::Custom<String>> {
+    sparql_query(form.into_inner().query, accept).await
+}
+
+#[post("/sparql", format = "application/sparql-query", data = "<query>")]
+async fn sparql_post_query(query : String, accept : sparql::SparqlAccept) -> Result<(ContentType, Vec<u8>), status::Custom<String>> {
+    sparql_query(query, accept).await
+}
+
+/// Answer a SPARQL query on a blocking thread, loading the triple store on
+/// first use. The request fails if the query takes longer than the timeout,
+/// but the query holds its permit until its evaluation stops, so abandoned
+/// queries cannot pile up
+async fn sparql_query(query : String, accept : sparql::SparqlAccept) -> Result<(ContentType, Vec<u8>), status::Custom<String>> {
+    let state = STATE.get().expect("State not set");
+    let internal = |e : String| status::Custom(Status::InternalServerError, e);
+    let store = rocket::tokio::task::spawn_blocking(move || {
+        state.sparql.get_or_try_init(|| {
+            let mut ntriples = Vec::new();
+            write_rdf(state, &mut rdf::NTriplesWriter::new(&mut ntriples))?;
+            sparql::SparqlStore::load(&ntriples).map_err(|e| e.to_string())
+        })
+    }).await.map_err(|e| internal(e.to_string()))?.map_err(internal)?;
+    let deadline = std::time::Instant::now() + sparql::QUERY_TIMEOUT;
+    let permit = store.permit().map_err(|e| status::Custom(Status::ServiceUnavailable, e.to_string()))?;
+    let task = rocket::tokio::task::spawn_blocking(move || {
+        let result = store.query(&query, &accept, deadline);
+        drop(permit);
+        result
     });
-    let results = results.iter().take(100).collect::<Vec<_>>();
-    RawJson(serde_json::to_string(&results).expect("Failed to serialize"))
+    let result = rocket::tokio::time::timeout(sparql::QUERY_TIMEOUT, task).await
+        .map_err(|_| sparql::SparqlError::Timeout)
+        .and_then(|r| r.map_err(|e| sparql::SparqlError::Evaluation(e.to_string())))
+        .and_then(|r| r);
+    let (media_type, data) = result.map_err(|e| match e {
+        sparql::SparqlError::Syntax(_) => status::Custom(Status::BadRequest, e.to_string()),
+        sparql::SparqlError::Timeout => status::Custom(Status::ServiceUnavailable, e.to_string()),
+        _ => internal(e.to_string())
+    })?;
+    Ok((ContentType::parse_flexible(media_type).unwrap_or(ContentType::Plain), data))
 }
 
 #[derive(Serialize)]
@@ -209,17 +744,11 @@ struct SynsetData {
 #[get("/autocomplete_synset/<query>")]
 fn autocomplete_synset(query: &str) -> RawJson<String> {
     let state = STATE.get().expect("State not set");
-    let mut lemmas = state.wn.lemma_by_prefix(query);
-    lemmas.sort_by(|a, b| {
-        match a.to_lowercase().cmp(&b.to_lowercase()) {
-            std::cmp::Ordering::Equal => a.cmp(b).reverse(),
-            x => x
-        }
-    });
+    let lemmas = state.wn().lemma_by_prefix(query, 100);
     let mut results = Vec::new();
     for lemma in lemmas {
-        for ssid in state.wn.entry_by_lemma(&lemma).iter() {
-            if let Some(synset) = state.wn.synset_by_id(ssid) {
+        for ssid in state.wn().entry_by_lemma(&lemma).iter() {
+            if let Some(synset) = state.wn().synset_by_id(ssid) {
                 results.push(SynsetData {
                     title: format!("{} - {}", lemma, synset.definition[0]),
                     lemma: lemma.clone(),
@@ -240,13 +769,41 @@ fn autocomplete_synset(query: &str) -> RawJson<String> {
 struct JsonResponse {
     synsets: Vec<MemberSynset>,
     target_labels: HashMap<String, String>,
+    /// The lemmas of other languages by ILI and language
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    translations: HashMap<String, BTreeMap<String, Vec<String>>>,
+    /// The CILI entries of the ILIs
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    ili: HashMap<String, cili::IliEntry>,
 }
 
 impl JsonResponse {
     fn new() -> Self {
         JsonResponse {
             synsets: Vec::new(),
-            target_labels: HashMap::new()
+            target_labels: HashMap::new(),
+            translations: HashMap::new(),
+            ili: HashMap::new()
+        }
+    }
+
+    fn add_cili(&mut self, cili : &cili::Cili) {
+        for synset in self.synsets.iter() {
+            if let Some(ili) = synset.ili.as_ref() {
+                if let Some(entry) = cili.get(ili.as_str()) {
+                    self.ili.insert(ili.as_str().to_string(), entry.clone());
+                }
+            }
+        }
+    }
+
+    fn add_translations(&mut self, translations : &omw::Translations) {
+        for synset in self.synsets.iter() {
+            if let Some(ili) = synset.ili.as_ref() {
+                if let Some(lemmas) = translations.by_ili(ili.as_str()) {
+                    self.translations.insert(ili.as_str().to_string(), lemmas.clone());
+                }
+            }
         }
     }
 
@@ -265,76 +822,171 @@ impl JsonResponse {
     fn merge(&mut self, other : JsonResponse) {
         self.synsets.extend(other.synsets);
         self.target_labels.extend(other.target_labels);
+        self.translations.extend(other.translations);
+        self.ili.extend(other.ili);
     }
 }
 
-fn resolve_query<'a>(state: &'a State, index : &str, id : &str) -> Result<JsonResponse, String> {
+fn resolve_query(wn : &Lexicon, index : &str, id : &str) -> Result<JsonResponse, String> {
     let mut response = JsonResponse::new();
     if index == "id" {
         let ssid = SynsetId::new(id);
-        if let Some(synset) = state.wn.synset_by_id(&ssid) {
+        if let Some(synset) = wn.synset_by_id(&ssid) {
             response.synsets.push(synset);
         }
     } else if index == "lemma" {
-        let entries = state.wn.entry_by_lemma(id);
+        let entries = wn.entry_by_form(id);
         for synset in entries.iter() {
-            if let Some(synset) = state.wn.synset_by_id(&synset) {
+            if let Some(synset) = wn.synset_by_id(&synset) {
                 response.synsets.push(synset);
             } else {
                 return Err(format!("Failed to find synset {:?}", synset));
             }
         }
     } else if index == "ili" {
-        if let Some((_, synset)) = state.wn.synset_by_ili(id) {
+        if let Some((_, synset)) = wn.synset_by_ili(id) {
             response.synsets.push(synset);
         }
+    } else if index == "sense" {
+        if let Some(ssid) = wn.synset_id_by_sense(id) {
+            if let Some(synset) = wn.synset_by_id(ssid) {
+                response.synsets.push(synset);
+            }
+        }
+    } else if index == "wikidata" {
+        for ssid in wn.synsets_by_wikidata(id) {
+            if let Some(synset) = wn.synset_by_id(&ssid) {
+                response.synsets.push(synset);
+            }
+        }
     } else {
         return Err("Invalid index".to_string())
     }
     Ok(response)
 }
 
+/// Look up an edition by name for the versioned routes
+fn edition_lexicon<'a>(state : &'a State, edition : &str) -> Result<&'a Lexicon, String> {
+    state.lexicon(edition).ok_or_else(|| format!("Unknown edition {}", edition))
+}
+
 #[get("/json/<index>/<id>")]
 fn json(index: &str, id: &str) -> Result<RawJson<String>, String> {
     let state = STATE.get().expect("State not set");
-    let mut response = resolve_query(&state, index, id)?;
-    response.add_targets(&state.wn);
+    json_for(state, state.wn(), index, id)
+}
+
+#[get("/json/<edition>/<index>/<id>")]
+fn json_edition(edition: &str, index: &str, id: &str) -> Result<RawJson<String>, String> {
+    let state = STATE.get().expect("State not set");
+    json_for(state, edition_lexicon(state, edition)?, index, id)
+}
+
+fn json_for(state : &State, wn : &Lexicon, index : &str, id : &str) -> Result<RawJson<String>, String> {
+    let mut response = resolve_query(wn, index, id)?;
+    response.add_targets(wn);
+    response.add_translations(&state.translations);
+    response.add_cili(&state.cili);
     Ok(RawJson(serde_json::to_string(&response).map_err(|e| format!("Failed to serialize: {}", e))?))
 }
 
 #[get("/ttl/<index>/<query>")]
 fn turtle(index : &str, query : &str) -> Result<(ContentType, String) , String> {
     let state = STATE.get().expect("State not set");
-    let response = resolve_query(&state, index, query)?;
-    let hb_data = hbs::make_synsets_hb(response.synsets, index, query);
-    let mut content = String::new();
-    content.push_str(&state.handlebars.render("ttl-header", &hb_data).map_err(|e| format!("Failed to render template: {}", e))?);
-    content.push_str(&state.handlebars.render("ttl", &hb_data).map_err(|e| format!("Failed to render template: {}", e))?);
-    Ok((ContentType::new("text", "turtle"), content))
+    turtle_for(state, state.wn(), index, query)
+}
+
+#[get("/ttl/<edition>/<index>/<query>")]
+fn turtle_edition(edition : &str, index : &str, query : &str) -> Result<(ContentType, String) , String> {
+    let state = STATE.get().expect("State not set");
+    turtle_for(state, edition_lexicon(state, edition)?, index, query)
+}
+
+fn turtle_for(state : &State, wn : &Lexicon, index : &str, query : &str) -> Result<(ContentType, String) , String> {
+    let response = resolve_query(wn, index, query)?;
+    let graph = hbs::make_synsets_hb(response.synsets, index, query, &state.translations).graph();
+    let mut content = Vec::new();
+    let mut writer = rdf::TurtleWriter::new(&mut content, &hbs::namespaces())
+        .map_err(|e| format!("Failed to write Turtle: {}", e))?;
+    writer.write_graph(&graph).map_err(|e| format!("Failed to write Turtle: {}", e))?;
+    writer.finish().map_err(|e| format!("Failed to write Turtle: {}", e))?;
+    Ok((ContentType::new("text", "turtle"),
+        String::from_utf8(content).map_err(|e| format!("Failed to write Turtle: {}", e))?))
 }
 
 #[get("/rdf/<index>/<query>")]
 fn rdfxml(index : &str, query : &str) -> Result<(ContentType, String) , String> {
     let state = STATE.get().expect("State not set");
-    let response = resolve_query(&state, index, query)?;
-    let hb_data = hbs::make_synsets_hb(response.synsets, index, query);
+    rdfxml_for(state, state.wn(), index, query)
+}
+
+#[get("/rdf/<edition>/<index>/<query>")]
+fn rdfxml_edition(edition : &str, index : &str, query : &str) -> Result<(ContentType, String) , String> {
+    let state = STATE.get().expect("State not set");
+    rdfxml_for(state, edition_lexicon(state, edition)?, index, query)
+}
+
+fn rdfxml_for(state : &State, wn : &Lexicon, index : &str, query : &str) -> Result<(ContentType, String) , String> {
+    let response = resolve_query(wn, index, query)?;
+    let graph = hbs::make_synsets_hb(response.synsets, index, query, &state.translations).graph();
+    let mut content = Vec::new();
+    let mut writer = rdf::RdfXmlWriter::new(&mut content, &hbs::namespaces())
+        .map_err(|e| format!("Failed to write RDF/XML: {}", e))?;
+    writer.write_graph(&graph).map_err(|e| format!("Failed to write RDF/XML: {}", e))?;
+    writer.finish().map_err(|e| format!("Failed to write RDF/XML: {}", e))?;
     Ok((ContentType::new("application", "rdf+xml"),
-        state.handlebars.render("rdfxml", &hb_data).map_err(|e| format!("Failed to render template: {}", e))?))
+        String::from_utf8(content).map_err(|e| format!("Failed to write RDF/XML: {}", e))?))
+}
+
+#[get("/jsonld/<index>/<query>")]
+fn jsonld(index : &str, query : &str) -> Result<(ContentType, String) , String> {
+    let state = STATE.get().expect("State not set");
+    jsonld_for(state, state.wn(), index, query)
+}
+
+#[get("/jsonld/<edition>/<index>/<query>")]
+fn jsonld_edition(edition : &str, index : &str, query : &str) -> Result<(ContentType, String) , String> {
+    let state = STATE.get().expect("State not set");
+    jsonld_for(state, edition_lexicon(state, edition)?, index, query)
+}
+
+fn jsonld_for(state : &State, wn : &Lexicon, index : &str, query : &str) -> Result<(ContentType, String) , String> {
+    let response = resolve_query(wn, index, query)?;
+    let graph = hbs::make_synsets_hb(response.synsets, index, query, &state.translations).graph();
+    let mut content = Vec::new();
+    let mut writer = rdf::JsonLdWriter::new(&mut content, &hbs::namespaces(), &hbs::jsonld_terms())
+        .map_err(|e| format!("Failed to write JSON-LD: {}", e))?;
+    writer.write_graph(&graph).map_err(|e| format!("Failed to write JSON-LD: {}", e))?;
+    writer.finish().map_err(|e| format!("Failed to write JSON-LD: {}", e))?;
+    Ok((ContentType::new("application", "ld+json"),
+        String::from_utf8(content).map_err(|e| format!("Failed to write JSON-LD: {}", e))?))
 }
 
 #[get("/xml/<index>/<query>")]
 fn xml(index : &str, query: &str) -> Result<(ContentType, String) , String> {
     let state = STATE.get().expect("State not set");
-    let response = resolve_query(&state, index, query)?;
-    let hb_data = hbs::make_synsets_hb(response.synsets, index, query);
+    xml_for(state, state.wn(), &state.default_edition, index, query)
+}
+
+#[get("/xml/<edition>/<index>/<query>")]
+fn xml_edition(edition : &str, index : &str, query: &str) -> Result<(ContentType, String) , String> {
+    let state = STATE.get().expect("State not set");
+    xml_for(state, edition_lexicon(state, edition)?, edition, index, query)
+}
+
+fn xml_for(state : &State, wn : &Lexicon, edition : &str, index : &str, query: &str) -> Result<(ContentType, String) , String> {
+    let response = resolve_query(wn, index, query)?;
+    let mut content = Vec::new();
+    lmf::write_synsets(&mut content, wn, edition, &response.synsets, &state.translations)
+        .map_err(|e| format!("Failed to write XML: {}", e))?;
     Ok((ContentType::new("application", "xml"),
-        state.handlebars.render("xml", &hb_data).map_err(|e| format!("Failed to render template: {}", e))?))
+        String::from_utf8(content).map_err(|e| format!("Failed to write XML: {}", e))?))
 }
 
 #[get("/html_index/<ssid>")]
 fn html_synset(ssid : &str) -> Result<RawHtml<String>, String> {
     let state = STATE.get().expect("State not set");
-    if let Some(synset) = state.wn.synset_by_id(&SynsetId::new(ssid)) {
+    if let Some(synset) = state.wn().synset_by_id(&SynsetId::new(ssid)) {
         let content = state.handlebars.render("html", &synset).map_err(|e| format!("Failed to render template: {}", e))?;
         Ok(RawHtml(content))
     } else {
@@ -347,20 +999,52 @@ fn ids(id : Vec<&str>) -> Result<RawJson<String>, String> {
     let state = STATE.get().expect("State not set");
     let mut response = JsonResponse::new();
     for i in id {
-        response.merge(resolve_query(&state, "id", i)?);
+        response.merge(resolve_query(state.wn(), "id", i)?);
     }
-    response.add_targets(&state.wn);
+    response.add_targets(state.wn());
+    response.add_translations(&state.translations);
+    response.add_cili(&state.cili);
     Ok(RawJson(serde_json::to_string(&response).map_err(|e| format!("Failed to serialize: {}", e))?))
 }
 
 
-fn dump_ttl(file : &str) -> Result<(), String> {
-    let mut f = std::fs::File::create(file).map_err(|e| format!("Failed to open file: {}", e))?;
+fn dump(file : &str, format : &DumpFormat, gzip : bool) -> Result<(), String> {
+    let f = std::fs::File::create(file).map_err(|e| format!("Failed to open file: {}", e))?;
     let state = STATE.get().expect("State not set");
-    let data = hbs::SynsetsHB::all(&state.wn);
-    state.handlebars.render_to_write("ttl-header", &data, &mut f).map_err(|e| format!("Failed to render template: {}", e))?;
-    state.handlebars.render_to_write("ttl", &data, &mut f).map_err(|e| format!("Failed to render template: {}", e))?;
-    Ok(())
+    if gzip || file.ends_with(".gz") {
+        let mut encoder = GzEncoder::new(BufWriter::new(f), Compression::default());
+        dump_to(state, format, &mut encoder)?;
+        encoder.finish().and_then(|mut w| w.flush()).map_err(|e| format!("Failed to write file: {}", e))
+    } else {
+        let mut out = BufWriter::new(f);
+        dump_to(state, format, &mut out)?;
+        out.flush().map_err(|e| format!("Failed to write file: {}", e))
+    }
+}
+
+/// Write the whole lexicon in a dump format, one synset at a time
+fn dump_to<W : Write>(state : &State, format : &DumpFormat, out : W) -> Result<(), String> {
+    let namespaces = hbs::namespaces();
+    let err = |e : std::io::Error| format!("Failed to write dump: {}", e);
+    match format {
+        DumpFormat::Ttl => write_rdf(state, &mut rdf::TurtleWriter::new(out, &namespaces).map_err(err)?),
+        DumpFormat::Nt => write_rdf(state, &mut rdf::NTriplesWriter::new(out)),
+        DumpFormat::Rdfxml => write_rdf(state, &mut rdf::RdfXmlWriter::new(out, &namespaces).map_err(err)?),
+        DumpFormat::Jsonld => write_rdf(state, &mut rdf::JsonLdWriter::new(out, &namespaces, &hbs::jsonld_terms()).map_err(err)?),
+        DumpFormat::LmfXml => lmf::write_lexicon(out, state.wn(), &state.default_edition).map_err(err)
+    }
+}
+
+/// Write the RDF view of every synset
+fn write_rdf<W : rdf::RdfWriter>(state : &State, writer : &mut W) -> Result<(), String> {
+    for synset_id in state.wn().synset_ids.iter() {
+        let synset = state.wn().synset_by_id(synset_id)
+            .ok_or_else(|| format!("Failed to find synset {:?}", synset_id))?;
+        let mut graph = rdf::Graph::new();
+        hbs::HBSynset::from(&synset).with_translations(&state.translations).add_to_graph(&mut graph);
+        writer.write_graph(&graph).map_err(|e| format!("Failed to write RDF: {}", e))?;
+    }
+    writer.finish().map_err(|e| format!("Failed to write RDF: {}", e))
 }
 
 #[derive(Serialize)]
@@ -404,13 +1088,29 @@ fn get_corpus(id : &str, offset : Option<usize>, limit : Option<usize>) -> Resul
     Ok(RawJson(serde_json::to_string(&results).map_err(|e| format!("Failed to serialize: {}", e))?))
 }
 
+/// Compare two editions that are not served
+fn diff_command(from : &str, to : &str) -> Result<(), String> {
+    let from = load_edition(Path::new(from), "wordnet-diff-from")?;
+    let to = load_edition(Path::new(to), "wordnet-diff-to")?;
+    let diff = diff::diff(&from, &to);
+    println!("{}", serde_json::to_string_pretty(&diff).map_err(|e| format!("Failed to serialize: {}", e))?);
+    Ok(())
+}
+
 #[launch]
 fn rocket() -> _ {
     let config = Config::parse();
+    if let Some(Command::Diff { from, to }) = &config.command {
+        if let Err(msg) = diff_command(from, to) {
+            eprintln!("{}", msg);
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
     match prepare_server(&config) {
         Ok(state) => {
-            if let Some(f) = &config.dump_ttl {
-                dump_ttl(f).expect("Failed to dump ttl");
+            if let Some(f) = &config.dump {
+                dump(f, &config.dump_format, config.gzip).expect("Failed to dump");
                 std::process::exit(0);
             }
             let mut rocket_config = RocketConfig::release_default();
@@ -420,12 +1120,18 @@ fn rocket() -> _ {
                 .manage(state)
                 .mount("/assets", FileServer::from("dist/assets"))
                 .mount("/", routes![index_page, json, autocomplete, 
-                    get_lemma, get_id, get_ili,
+                    get_lemma, get_edition_lemma, get_id, get_edition_id, get_ili, get_edition_ili,
+                    get_sense, get_edition_sense, get_pwn30, get_pwn31, get_wikidata,
                     favicon, downloads, turtle,
-                    rdfxml, xml, html_synset,
+                    rdfxml, jsonld, xml, html_synset,
+                    json_edition, turtle_edition, rdfxml_edition,
+                    jsonld_edition, xml_edition,
                     sitemap, robots,
                     autocomplete_synset, edit_page,
-                    edit_page2, ids, get_corpus, corpus])
+                    edit_page2, ids, get_corpus, corpus,
+                    post_changes, fuzzy_search, text_search,
+                    synset_ancestors, synset_descendants, synset_paths,
+                    similarity, edition_diff, synset_history, ili_report, sparql_get, sparql_post_form, sparql_post_query])
                     
         },
         Err(msg) => {
@@ -434,3 +1140,59 @@ fn rocket() -> _ {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocket::local::blocking::Client;
+    use wordnet::tests::{TAXONOMY, lexicon_from_lmf};
+
+    /// A client for the pages, serving the test taxonomy as edition `test`
+    fn client() -> Client {
+        STATE.get_or_init(|| {
+            let mut handlebars = Handlebars::new();
+            handlebars.register_template_string("html", include_str!("hbs/html.hbs")).unwrap();
+            let editions = HashMap::from([("test".to_string(), lexicon_from_lmf("routes", TAXONOMY))]);
+            State { editions, edition_order: vec!["test".to_string()], handlebars,
+                corpora: HashMap::new(), synset_counts: HashMap::new(), wn_folder: None,
+                edition: "test".to_string(), default_edition: "test".to_string(), edit_token: None,
+                edits: Mutex::new(changes::Edits::default()), translations: omw::Translations::default(),
+                cili: cili::Cili::default(), information_content: OnceCell::new(), sparql: OnceCell::new() }
+        });
+        Client::untracked(rocket::build().mount("/", routes![get_lemma, get_edition_lemma,
+            get_id, get_edition_id, get_ili, get_edition_ili])).unwrap()
+    }
+
+    fn location<'a>(client : &'a Client, uri : &'a str) -> Option<String> {
+        let response = client.get(uri).dispatch();
+        assert_eq!(response.status(), Status::SeeOther, "{}", uri);
+        response.headers().get_one("Location").map(|l| l.to_string())
+    }
+
+    #[test]
+    fn format_suffix() {
+        let client = client();
+        assert_eq!(location(&client, "/id/00000003-n.ttl").as_deref(), Some("/ttl/id/00000003-n"));
+        assert_eq!(location(&client, "/id/00000003-n.jsonld").as_deref(), Some("/jsonld/id/00000003-n"));
+        assert_eq!(location(&client, "/lemma/dog.jsonld").as_deref(), Some("/jsonld/lemma/dog"));
+        assert_eq!(location(&client, "/lemma/dog.rdf").as_deref(), Some("/rdf/lemma/dog"));
+        assert_eq!(location(&client, "/ili/i46360.json").as_deref(), Some("/json/ili/i46360"));
+        assert_eq!(location(&client, "/test/id/00000003-n.ttl").as_deref(), Some("/ttl/test/id/00000003-n"));
+        assert_eq!(location(&client, "/test/lemma/dog.ttl").as_deref(), Some("/ttl/test/lemma/dog"));
+        assert_eq!(location(&client, "/test/ili/i46360.rdf").as_deref(), Some("/rdf/test/ili/i46360"));
+        assert_eq!(client.get("/id/00000003-n.html").dispatch().status(), Status::Ok);
+        assert_eq!(client.get("/id/00000099-n.ttl").dispatch().status(), Status::NotFound);
+        assert_eq!(client.get("/lemma/wolf.jsonld").dispatch().status(), Status::NotFound);
+    }
+
+    #[test]
+    fn accept_header() {
+        let client = client();
+        let response = client.get("/id/00000003-n").header(rocket::http::Header::new("Accept", "text/turtle")).dispatch();
+        assert_eq!(response.status(), Status::SeeOther);
+        assert_eq!(response.headers().get_one("Location"), Some("/ttl/id/00000003-n"));
+        // The suffix wins over the header
+        let response = client.get("/id/00000003-n.rdf").header(rocket::http::Header::new("Accept", "text/turtle")).dispatch();
+        assert_eq!(response.headers().get_one("Location"), Some("/rdf/id/00000003-n"));
+    }
+}
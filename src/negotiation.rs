@@ -2,7 +2,8 @@ use rocket::request::{FromRequest, Request, Outcome};
 use rocket::response::{Redirect, Responder};
 use rocket::response::content::RawHtml;
 
-pub enum ContentNegotiation { Html, RdfXml, Turtle, Json }
+#[derive(Debug, PartialEq)]
+pub enum ContentNegotiation { Html, RdfXml, Turtle, Json, JsonLd }
 
 #[rocket::async_trait]
 impl<'r> FromRequest<'r> for ContentNegotiation {
@@ -17,6 +18,8 @@ impl<'r> FromRequest<'r> for ContentNegotiation {
                 return Outcome::Success(ContentNegotiation::Turtle);
             } else if value.starts_with("application/x-turtle") {
                 return Outcome::Success(ContentNegotiation::Turtle);
+            } else if value.starts_with("application/ld+json") {
+                return Outcome::Success(ContentNegotiation::JsonLd);
             } else if value.starts_with("application/json") {
                 return Outcome::Success(ContentNegotiation::Json);
             } else if value.starts_with("application/javascript") {
@@ -35,31 +38,44 @@ pub enum NegotiatedResponse {
 }
 
 
-pub fn negotiated(idx : &'static str, key : &str, index: &'static str, neg : ContentNegotiation) -> NegotiatedResponse {
-    if key.ends_with(".rdf") {
-        negotiated(idx,&key[0..(key.len()-4)], index, ContentNegotiation::RdfXml)
-    } else if key.ends_with(".ttl") {
-        negotiated(idx,&key[0..(key.len()-4)], index, ContentNegotiation::Turtle)
-    } else if key.ends_with(".json") {
-        negotiated(idx,&key[0..(key.len()-5)], index, ContentNegotiation::Json)
-    } else if key.ends_with(".html") {
-        negotiated(idx,&key[0..(key.len()-5)], index, ContentNegotiation::Html)
+/// Split a format suffix, such as `.ttl`, off a key. The suffix overrides
+/// the format asked for in the `Accept` header
+pub fn split_format(key : &str, neg : ContentNegotiation) -> (&str, ContentNegotiation) {
+    if let Some(key) = key.strip_suffix(".rdf") {
+        (key, ContentNegotiation::RdfXml)
+    } else if let Some(key) = key.strip_suffix(".ttl") {
+        (key, ContentNegotiation::Turtle)
+    } else if let Some(key) = key.strip_suffix(".jsonld") {
+        (key, ContentNegotiation::JsonLd)
+    } else if let Some(key) = key.strip_suffix(".json") {
+        (key, ContentNegotiation::Json)
+    } else if let Some(key) = key.strip_suffix(".html") {
+        (key, ContentNegotiation::Html)
     } else {
-        match neg {
-            ContentNegotiation::Html => { 
-                NegotiatedResponse::Html(RawHtml(index))
-            },
-            ContentNegotiation::RdfXml => {
-                NegotiatedResponse::Redirect(Redirect::to(format!("/rdf/{}/{}", idx, key)))
-            },
-            ContentNegotiation::Turtle => {
-                NegotiatedResponse::Redirect(Redirect::to(format!("/ttl/{}/{}", idx, key)))
-            },
-            ContentNegotiation::Json => {
-                NegotiatedResponse::Redirect(Redirect::to(format!("/json/{}/{}", idx, key)))
-            }
-        }
+        (key, neg)
     }
 }
 
- 
+/// Serve the HTML page, or redirect to the data in the requested format.
+/// The redirect keeps the edition, if one was given. The key must already
+/// have had its format suffix removed by `split_format`
+pub fn negotiated(idx : &'static str, key : &str, edition : Option<&str>, index: &'static str, neg : ContentNegotiation) -> NegotiatedResponse {
+    let prefix = edition.map(|e| format!("{}/", e)).unwrap_or_default();
+    match neg {
+        ContentNegotiation::Html => { 
+            NegotiatedResponse::Html(RawHtml(index))
+        },
+        ContentNegotiation::RdfXml => {
+            NegotiatedResponse::Redirect(Redirect::to(format!("/rdf/{}{}/{}", prefix, idx, key)))
+        },
+        ContentNegotiation::Turtle => {
+            NegotiatedResponse::Redirect(Redirect::to(format!("/ttl/{}{}/{}", prefix, idx, key)))
+        },
+        ContentNegotiation::Json => {
+            NegotiatedResponse::Redirect(Redirect::to(format!("/json/{}{}/{}", prefix, idx, key)))
+        },
+        ContentNegotiation::JsonLd => {
+            NegotiatedResponse::Redirect(Redirect::to(format!("/jsonld/{}{}/{}", prefix, idx, key)))
+        }
+    }
+}
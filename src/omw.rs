@@ -0,0 +1,161 @@
+/// The lemmas of wordnets in other languages, joined to OEWN through the ILI
+///
+
+use crate::lmf::{LmfError, read_lmf_lemmas};
+use crate::rdf::is_language_tag;
+use crate::wordnet::ILIID;
+use flate2::read::GzDecoder;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use thiserror::Error;
+
+/// Where the lemmas of a language come from
+#[derive(Clone, Debug, Default)]
+pub struct TranslationSource {
+    pub label : String,
+    pub email : String,
+    pub license : String,
+    pub version : String,
+}
+
+/// The lemmas of each language for each ILI
+#[derive(Default)]
+pub struct Translations {
+    lemmas : HashMap<String, BTreeMap<String, Vec<String>>>,
+    sources : BTreeMap<String, TranslationSource>,
+}
+
+impl Translations {
+    /// Load a wordnet in the OMW tab format (`.tab`) or in WN-LMF (any other
+    /// file, which may be gzipped). The synsets of a tab file are PWN 3.0
+    /// offsets, which are mapped to ILIs with `ili_map`, unless they are
+    /// already ILIs.
+    pub fn load(&mut self, file : &Path, ili_map : &HashMap<String, String>) -> Result<(), TranslationError> {
+        let file_name = file.file_name().and_then(|x| x.to_str()).unwrap_or("").to_string();
+        if file_name.ends_with(".tab") {
+            let data = fs::read_to_string(file)
+                .map_err(|e| TranslationError::Io(format!("Error reading {} due to {}", file_name, e)))?;
+            self.load_tab(&data, &file_name, ili_map)
+        } else {
+            let input = File::open(file)
+                .map_err(|e| TranslationError::Io(format!("Error reading {} due to {}", file_name, e)))?;
+            let lexicons = if file_name.ends_with(".gz") {
+                read_lmf_lemmas(BufReader::new(GzDecoder::new(input)))?
+            } else {
+                read_lmf_lemmas(BufReader::new(input))?
+            };
+            for lexicon in lexicons {
+                check_language(&lexicon.language, &file_name)?;
+                self.sources.insert(lexicon.language.clone(), TranslationSource {
+                    label: lexicon.label,
+                    email: lexicon.email,
+                    license: lexicon.license,
+                    version: lexicon.version,
+                });
+                for (ili, lemma) in lexicon.lemmas {
+                    self.add(ili, &lexicon.language, lemma);
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Read the OMW tab format, where the header gives the label, language,
+    /// URL and license and each line is `synset<TAB>lang:lemma<TAB>lemma`
+    fn load_tab(&mut self, data : &str, file_name : &str, ili_map : &HashMap<String, String>) -> Result<(), TranslationError> {
+        let mut lines = data.lines();
+        let header = lines.next()
+            .and_then(|l| l.strip_prefix('#'))
+            .ok_or_else(|| TranslationError::Format(format!("{} has no header", file_name)))?;
+        let header : Vec<&str> = header.split('\t').map(|x| x.trim()).collect();
+        let language = header.get(1)
+            .ok_or_else(|| TranslationError::Format(format!("{} has no language in its header", file_name)))?
+            .to_string();
+        check_language(&language, file_name)?;
+        self.sources.insert(language.clone(), TranslationSource {
+            label: header[0].to_string(),
+            license: header.get(3).map(|x| x.to_string()).unwrap_or_default(),
+            ..TranslationSource::default()
+        });
+        for (no, line) in lines.enumerate() {
+            if line.starts_with('#') || line.trim().is_empty() {
+                continue;
+            }
+            let cols : Vec<&str> = line.split('\t').collect();
+            if cols.len() < 3 {
+                return Err(TranslationError::Format(format!("{} line {}: expected three columns", file_name, no + 2)));
+            }
+            if !cols[1].ends_with(":lemma") {
+                continue;
+            }
+            let ili = if is_ili(cols[0]) {
+                Some(cols[0].to_string())
+            } else {
+                ili_map.get(cols[0]).or_else(|| ili_map.get(&cols[0].replace("-s", "-a"))).cloned()
+            };
+            if let Some(ili) = ili {
+                self.add(ili, &language, cols[2].trim().to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, ili : String, language : &str, lemma : String) {
+        let lemmas = self.lemmas.entry(ili).or_default()
+            .entry(language.to_string()).or_default();
+        if !lemmas.contains(&lemma) {
+            lemmas.push(lemma);
+        }
+    }
+
+    /// The lemmas of each language for an ILI
+    pub fn by_ili(&self, ili : &str) -> Option<&BTreeMap<String, Vec<String>>> {
+        self.lemmas.get(ili)
+    }
+
+    /// The source of the lemmas of a language
+    pub fn source(&self, language : &str) -> Option<&TranslationSource> {
+        self.sources.get(language)
+    }
+}
+
+/// Read a CILI mapping from PWN 3.0 offsets to ILIs, with lines such as
+/// `i1<TAB>00001740-a`
+pub fn load_ili_map(file : &Path) -> Result<HashMap<String, String>, TranslationError> {
+    let data = fs::read_to_string(file)
+        .map_err(|e| TranslationError::Io(format!("Error reading {} due to {}", file.display(), e)))?;
+    let mut map = HashMap::new();
+    for line in data.lines() {
+        if let Some((ili, offset)) = line.split_once('\t') {
+            if is_ili(ili) {
+                map.insert(offset.trim().to_string(), ili.to_string());
+            }
+        }
+    }
+    Ok(map)
+}
+
+fn is_ili(s : &str) -> bool {
+    ILIID::parse(s).map(|ili| !ili.is_new()).unwrap_or(false)
+}
+
+/// The language is written as a language tag in RDF, so must be valid BCP 47
+fn check_language(language : &str, file_name : &str) -> Result<(), TranslationError> {
+    if is_language_tag(language) {
+        Ok(())
+    } else {
+        Err(TranslationError::Format(format!("{} has the invalid language tag {}", file_name, language)))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum TranslationError {
+    #[error("Could not load translations: {0}")]
+    Io(String),
+    #[error("Could not load translations: {0}")]
+    Format(String),
+    #[error("Could not load translations: {0}")]
+    Lmf(#[from] LmfError),
+}
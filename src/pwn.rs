@@ -0,0 +1,77 @@
+/// Mappings from the synset offsets of Princeton WordNet 3.0 and 3.1 to OEWN
+///
+
+use crate::wordnet::{ILIID, Lexicon, SynsetId};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+/// A Princeton WordNet release
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PwnVersion { Pwn30, Pwn31 }
+
+impl PwnVersion {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            PwnVersion::Pwn30 => "pwn30",
+            PwnVersion::Pwn31 => "pwn31"
+        }
+    }
+}
+
+/// Read a table mapping offsets to synsets, either with lines of
+/// `offset-pos<TAB>synset` or as a CILI mapping with lines of
+/// `ili<TAB>offset-pos`, which is joined through the ILIs of the lexicon.
+/// An offset maps to several synsets if its synset was split.
+pub fn read_mapping(file : &Path, wn : &Lexicon) -> Result<HashMap<String, Vec<SynsetId>>, PwnMapError> {
+    let file_name = file.file_name().and_then(|x| x.to_str()).unwrap_or("").to_string();
+    let data = fs::read_to_string(file)
+        .map_err(|e| PwnMapError::Io(format!("Error reading {} due to {}", file_name, e)))?;
+    let mut mapping : HashMap<String, Vec<SynsetId>> = HashMap::new();
+    for (no, line) in data.lines().enumerate() {
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        let cols : Vec<&str> = line.split_whitespace().collect();
+        if cols.len() < 2 {
+            return Err(PwnMapError::Format(format!("{} line {}: expected two columns", file_name, no + 1)));
+        }
+        let (offset, target) = match ILIID::parse(cols[0]) {
+            Ok(ili) if !ili.is_new() => (cols[1], wn.synset_id_by_ili(ili.as_str()).cloned()),
+            _ => (cols[0], Some(SynsetId::new(cols[1].strip_prefix("oewn-").unwrap_or(cols[1]))))
+        };
+        let key = offset_key(offset)
+            .ok_or_else(|| PwnMapError::Format(format!("{} line {}: bad synset offset {}", file_name, no + 1, offset)))?;
+        if let Some(target) = target {
+            let targets = mapping.entry(key).or_default();
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+    }
+    Ok(mapping)
+}
+
+/// Normalize an offset such as `1740-s` to `00001740-a`, as satellites share
+/// the offsets of adjectives
+pub fn offset_key(offset : &str) -> Option<String> {
+    let (number, pos) = offset.trim().rsplit_once('-')?;
+    if number.is_empty() || number.len() > 8 || !number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let pos = match pos {
+        "n" | "v" | "r" => pos,
+        "a" | "s" => "a",
+        _ => return None
+    };
+    Some(format!("{:0>8}-{}", number, pos))
+}
+
+#[derive(Error, Debug)]
+pub enum PwnMapError {
+    #[error("Could not load PWN mapping: {0}")]
+    Io(String),
+    #[error("Could not load PWN mapping: {0}")]
+    Format(String),
+}
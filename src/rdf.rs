@@ -0,0 +1,754 @@
+/// Typed RDF graphs and writers for Turtle, N-Triples, RDF/XML and JSON-LD
+///
+
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+pub const RDF : &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+pub const RDF_TYPE : &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+
+/// A node or value in an RDF graph
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Term {
+    Iri(String),
+    /// A blank node, numbered within its graph
+    Blank(usize),
+    /// A literal with an optional language tag
+    Literal(String, Option<String>),
+}
+
+impl Term {
+    pub fn iri<S : Into<String>>(iri : S) -> Term {
+        Term::Iri(iri.into())
+    }
+
+    pub fn literal<S : Into<String>>(value : S) -> Term {
+        Term::Literal(value.into(), None)
+    }
+
+    pub fn lang_literal<S : Into<String>>(value : S, lang : &str) -> Term {
+        Term::Literal(value.into(), Some(lang.to_string()))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Triple {
+    pub subject : Term,
+    pub predicate : String,
+    pub object : Term,
+}
+
+/// A set of triples under construction
+#[derive(Clone, Debug, Default)]
+pub struct Graph {
+    triples : Vec<Triple>,
+    blanks : usize,
+}
+
+impl Graph {
+    pub fn new() -> Graph {
+        Graph::default()
+    }
+
+    /// Create a new blank node
+    pub fn blank(&mut self) -> Term {
+        self.blanks += 1;
+        Term::Blank(self.blanks - 1)
+    }
+
+    pub fn add<S : Into<String>>(&mut self, subject : &Term, predicate : S, object : Term) {
+        self.triples.push(Triple {
+            subject: subject.clone(),
+            predicate: predicate.into(),
+            object
+        });
+    }
+
+    /// The triples grouped by subject, in order of first appearance
+    fn by_subject(&self) -> Vec<(&Term, Vec<&Triple>)> {
+        let mut index : HashMap<&Term, usize> = HashMap::new();
+        let mut groups : Vec<(&Term, Vec<&Triple>)> = Vec::new();
+        for triple in self.triples.iter() {
+            let i = *index.entry(&triple.subject).or_insert_with(|| {
+                groups.push((&triple.subject, Vec::new()));
+                groups.len() - 1
+            });
+            groups[i].1.push(triple);
+        }
+        groups
+    }
+}
+
+/// A serializer that writes a sequence of graphs as one document. Blank
+/// nodes are relabelled so that those of different graphs stay distinct
+pub trait RdfWriter {
+    fn write_graph(&mut self, graph : &Graph) -> io::Result<()>;
+    /// Write the end of the document
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+pub struct TurtleWriter<W : Write> {
+    out : W,
+    namespaces : Vec<(String, String)>,
+    blank_offset : usize,
+}
+
+impl<W : Write> TurtleWriter<W> {
+    pub fn new(mut out : W, namespaces : &[(String, String)]) -> io::Result<TurtleWriter<W>> {
+        for (prefix, ns) in namespaces.iter() {
+            writeln!(out, "@prefix {}: <{}> .", prefix, escape_iri(ns))?;
+        }
+        writeln!(out)?;
+        Ok(TurtleWriter { out, namespaces: namespaces.to_vec(), blank_offset: 0 })
+    }
+
+    fn term(&self, term : &Term) -> io::Result<String> {
+        match term {
+            Term::Iri(iri) => Ok(self.iri(iri)),
+            Term::Blank(n) => Ok(format!("_:b{}", self.blank_offset + n)),
+            Term::Literal(value, lang) => literal(value, lang)
+        }
+    }
+
+    fn iri(&self, iri : &str) -> String {
+        for (prefix, ns) in self.namespaces.iter() {
+            if let Some(local) = iri.strip_prefix(ns.as_str()) {
+                if is_turtle_local(local) {
+                    return format!("{}:{}", prefix, local);
+                }
+            }
+        }
+        format!("<{}>", escape_iri(iri))
+    }
+}
+
+impl<W : Write> RdfWriter for TurtleWriter<W> {
+    fn write_graph(&mut self, graph : &Graph) -> io::Result<()> {
+        for (subject, triples) in graph.by_subject() {
+            write!(self.out, "{}", self.term(subject)?)?;
+            for (i, triple) in triples.iter().enumerate() {
+                let predicate = if triple.predicate == RDF_TYPE {
+                    "a".to_string()
+                } else {
+                    self.iri(&triple.predicate)
+                };
+                let sep = if i + 1 == triples.len() { " ." } else { " ;" };
+                write!(self.out, "\n    {} {}{}", predicate, self.term(&triple.object)?, sep)?;
+            }
+            writeln!(self.out, "\n")?;
+        }
+        self.blank_offset += graph.blanks;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+pub struct NTriplesWriter<W : Write> {
+    out : W,
+    blank_offset : usize,
+}
+
+impl<W : Write> NTriplesWriter<W> {
+    pub fn new(out : W) -> NTriplesWriter<W> {
+        NTriplesWriter { out, blank_offset: 0 }
+    }
+
+    fn term(&self, term : &Term) -> io::Result<String> {
+        match term {
+            Term::Iri(iri) => Ok(format!("<{}>", escape_iri(iri))),
+            Term::Blank(n) => Ok(format!("_:b{}", self.blank_offset + n)),
+            Term::Literal(value, lang) => literal(value, lang)
+        }
+    }
+}
+
+impl<W : Write> RdfWriter for NTriplesWriter<W> {
+    fn write_graph(&mut self, graph : &Graph) -> io::Result<()> {
+        for triple in graph.triples.iter() {
+            writeln!(self.out, "{} <{}> {} .", self.term(&triple.subject)?,
+                escape_iri(&triple.predicate), self.term(&triple.object)?)?;
+        }
+        self.blank_offset += graph.blanks;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+pub struct RdfXmlWriter<W : Write> {
+    out : W,
+    namespaces : Vec<(String, String)>,
+    blank_offset : usize,
+}
+
+impl<W : Write> RdfXmlWriter<W> {
+    pub fn new(mut out : W, namespaces : &[(String, String)]) -> io::Result<RdfXmlWriter<W>> {
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"utf-8\"?>")?;
+        write!(out, "<rdf:RDF xmlns:rdf=\"{}\"", RDF)?;
+        let namespaces : Vec<(String, String)> = namespaces.iter()
+            .filter(|(prefix, _)| prefix != "rdf")
+            .cloned()
+            .collect();
+        for (prefix, ns) in namespaces.iter() {
+            write!(out, "\n         xmlns:{}=\"{}\"", prefix, escape_xml(ns, true))?;
+        }
+        writeln!(out, ">")?;
+        Ok(RdfXmlWriter { out, namespaces, blank_offset: 0 })
+    }
+
+    /// The element name of a property, with a namespace declaration if its
+    /// namespace was not declared in the header
+    fn property_name(&self, iri : &str) -> io::Result<(String, String)> {
+        if let Some(local) = iri.strip_prefix(RDF).filter(|l| is_xml_name(l)) {
+            return Ok((format!("rdf:{}", local), String::new()));
+        }
+        for (prefix, ns) in self.namespaces.iter() {
+            if let Some(local) = iri.strip_prefix(ns.as_str()).filter(|l| is_xml_name(l)) {
+                return Ok((format!("{}:{}", prefix, local), String::new()));
+            }
+        }
+        let split = iri.rfind(|c| c == '#' || c == '/').map(|i| i + 1).unwrap_or(0);
+        let (ns, local) = iri.split_at(split);
+        if ns.is_empty() || !is_xml_name(local) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData,
+                format!("Cannot write property {} in RDF/XML", iri)));
+        }
+        Ok((format!("ns0:{}", local), format!(" xmlns:ns0=\"{}\"", escape_xml(ns, true))))
+    }
+}
+
+impl<W : Write> RdfWriter for RdfXmlWriter<W> {
+    fn write_graph(&mut self, graph : &Graph) -> io::Result<()> {
+        for (subject, triples) in graph.by_subject() {
+            match subject {
+                Term::Blank(n) => writeln!(self.out, "  <rdf:Description rdf:nodeID=\"b{}\">", self.blank_offset + n)?,
+                Term::Iri(iri) => writeln!(self.out, "  <rdf:Description rdf:about=\"{}\">", escape_xml(&escape_iri(iri), true))?,
+                Term::Literal(_, _) => return Err(io::Error::new(io::ErrorKind::InvalidData,
+                    "A literal cannot be the subject of a triple"))
+            }
+            for triple in triples {
+                let (name, decl) = self.property_name(&triple.predicate)?;
+                match &triple.object {
+                    Term::Iri(iri) => writeln!(self.out, "    <{}{} rdf:resource=\"{}\"/>",
+                        name, decl, escape_xml(&escape_iri(iri), true))?,
+                    Term::Blank(n) => writeln!(self.out, "    <{}{} rdf:nodeID=\"b{}\"/>",
+                        name, decl, self.blank_offset + n)?,
+                    Term::Literal(value, Some(lang)) => writeln!(self.out, "    <{}{} xml:lang=\"{}\">{}</{}>",
+                        name, decl, checked_language(lang)?, escape_xml(value, false), name)?,
+                    Term::Literal(value, None) => writeln!(self.out, "    <{}{}>{}</{}>",
+                        name, decl, escape_xml(value, false), name)?
+                }
+            }
+            writeln!(self.out, "  </rdf:Description>")?;
+        }
+        self.blank_offset += graph.blanks;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        writeln!(self.out, "</rdf:RDF>")?;
+        self.out.flush()
+    }
+}
+
+/// The kind of value of a property named by a JSON-LD term
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonLdType {
+    /// Nodes, written as their IRI (`"@type": "@id"`)
+    Id,
+    /// Literals with this language tag (`"@language"`)
+    Language(String),
+    /// Literals without a language tag
+    String,
+}
+
+/// A term of a JSON-LD context, which names a property so that its values
+/// can be written as plain strings
+#[derive(Clone, Debug)]
+pub struct JsonLdTerm {
+    pub name : String,
+    pub iri : String,
+    pub value : JsonLdType,
+}
+
+impl JsonLdTerm {
+    pub fn new<S : Into<String>>(name : &str, iri : S, value : JsonLdType) -> JsonLdTerm {
+        JsonLdTerm { name: name.to_string(), iri: iri.into(), value }
+    }
+}
+
+/// Writes JSON-LD as a single `@graph` of node objects, with a context
+/// declaring the namespaces so that IRIs can be written as compact IRIs
+pub struct JsonLdWriter<W : Write> {
+    out : W,
+    namespaces : Vec<(String, String)>,
+    terms : Vec<JsonLdTerm>,
+    first : bool,
+    blank_offset : usize,
+}
+
+impl<W : Write> JsonLdWriter<W> {
+    /// Start a document whose context has the namespaces as prefixes and
+    /// the given terms. A value that does not fit the term of its property
+    /// (such as a literal in another language) is written in full under
+    /// the compact IRI of the property
+    pub fn new(mut out : W, namespaces : &[(String, String)], terms : &[JsonLdTerm]) -> io::Result<JsonLdWriter<W>> {
+        let context = serde_json::to_string(&jsonld_context(namespaces, terms))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        write!(out, "{{\n  \"@context\": {},\n  \"@graph\": [", context)?;
+        Ok(JsonLdWriter { out, namespaces: namespaces.to_vec(), terms: terms.to_vec(), first: true, blank_offset: 0 })
+    }
+
+    fn iri(&self, iri : &str) -> String {
+        compact_iri(&self.namespaces, iri)
+    }
+
+    /// The term and the value of a triple, if the context has a term for
+    /// its predicate that fits its object
+    fn term_value(&self, predicate : &str, object : &Term) -> Option<(String, Value)> {
+        let term = self.terms.iter().find(|t| t.iri == predicate)?;
+        let value = match (&term.value, object) {
+            (JsonLdType::Id, Term::Iri(_) | Term::Blank(_)) => self.node_id(object)?,
+            (JsonLdType::Language(lang), Term::Literal(value, Some(l))) if l == lang => value.clone(),
+            (JsonLdType::String, Term::Literal(value, None)) => value.clone(),
+            _ => return None
+        };
+        Some((term.name.clone(), Value::String(value)))
+    }
+
+    fn node_id(&self, term : &Term) -> Option<String> {
+        match term {
+            Term::Iri(iri) => Some(self.iri(iri)),
+            Term::Blank(n) => Some(format!("_:b{}", self.blank_offset + n)),
+            Term::Literal(_, _) => None
+        }
+    }
+
+    fn value(&self, term : &Term) -> io::Result<Value> {
+        let mut value = Map::new();
+        match term {
+            Term::Literal(v, lang) => {
+                value.insert("@value".to_string(), Value::String(v.clone()));
+                if let Some(lang) = lang {
+                    value.insert("@language".to_string(), Value::String(checked_language(lang)?.to_string()));
+                }
+            },
+            _ => {
+                value.insert("@id".to_string(), Value::String(self.node_id(term).unwrap_or_default()));
+            }
+        }
+        Ok(Value::Object(value))
+    }
+}
+
+impl<W : Write> RdfWriter for JsonLdWriter<W> {
+    fn write_graph(&mut self, graph : &Graph) -> io::Result<()> {
+        for (subject, triples) in graph.by_subject() {
+            let id = self.node_id(subject).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData,
+                "A literal cannot be the subject of a triple"))?;
+            let mut node = Map::new();
+            node.insert("@id".to_string(), Value::String(id));
+            for triple in triples {
+                let (key, value) = match (&triple.object, triple.predicate == RDF_TYPE) {
+                    (Term::Iri(iri), true) => ("@type".to_string(), Value::String(self.iri(iri))),
+                    _ => match self.term_value(&triple.predicate, &triple.object) {
+                        Some(term_value) => term_value,
+                        None => (self.iri(&triple.predicate), self.value(&triple.object)?)
+                    }
+                };
+                if let Value::Array(values) = node.entry(key).or_insert_with(|| Value::Array(Vec::new())) {
+                    values.push(value);
+                }
+            }
+            let json = serde_json::to_string(&Value::Object(node))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            if !self.first {
+                write!(self.out, ",")?;
+            }
+            write!(self.out, "\n    {}", json)?;
+            self.first = false;
+        }
+        self.blank_offset += graph.blanks;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        writeln!(self.out, "\n  ]\n}}")?;
+        self.out.flush()
+    }
+}
+
+/// A JSON-LD context mapping each prefix to its namespace and defining the
+/// terms
+fn jsonld_context(namespaces : &[(String, String)], terms : &[JsonLdTerm]) -> Value {
+    let mut context : Map<String, Value> = namespaces.iter()
+        .map(|(prefix, ns)| (prefix.clone(), Value::String(ns.clone())))
+        .collect();
+    for term in terms.iter() {
+        let mut definition = Map::new();
+        definition.insert("@id".to_string(), Value::String(compact_iri(namespaces, &term.iri)));
+        match &term.value {
+            JsonLdType::Id => {
+                definition.insert("@type".to_string(), Value::String("@id".to_string()));
+            },
+            JsonLdType::Language(lang) => {
+                definition.insert("@language".to_string(), Value::String(lang.clone()));
+            },
+            JsonLdType::String => ()
+        }
+        context.insert(term.name.clone(), Value::Object(definition));
+    }
+    Value::Object(context)
+}
+
+/// Shorten an IRI to `prefix:local` if it is in one of the namespaces
+fn compact_iri(namespaces : &[(String, String)], iri : &str) -> String {
+    let iri = escape_iri(iri);
+    for (prefix, ns) in namespaces.iter() {
+        if let Some(local) = iri.strip_prefix(ns.as_str()) {
+            if !local.is_empty() && !local.starts_with("//") {
+                return format!("{}:{}", prefix, local);
+            }
+        }
+    }
+    iri
+}
+
+/// Escape a literal for Turtle and N-Triples
+fn literal(value : &str, lang : &Option<String>) -> io::Result<String> {
+    let mut s = String::with_capacity(value.len() + 2);
+    s.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c if c.is_control() => s.push_str(&format!("\\u{:04X}", c as u32)),
+            c => s.push(c)
+        }
+    }
+    s.push('"');
+    if let Some(lang) = lang {
+        s.push('@');
+        s.push_str(checked_language(lang)?);
+    }
+    Ok(s)
+}
+
+fn checked_language(lang : &str) -> io::Result<&str> {
+    if is_language_tag(lang) {
+        Ok(lang)
+    } else {
+        Err(io::Error::new(io::ErrorKind::InvalidData, format!("Invalid language tag {}", lang)))
+    }
+}
+
+/// Whether a string is a well-formed BCP 47 language tag, such as `en`,
+/// `zh-Hant-TW` or `x-private` (the irregular grandfathered tags are not
+/// accepted)
+pub fn is_language_tag(tag : &str) -> bool {
+    let subtags : Vec<&str> = tag.split('-').collect();
+    if subtags.iter().any(|s| s.is_empty() || s.len() > 8 || !s.chars().all(|c| c.is_ascii_alphanumeric())) {
+        return false;
+    }
+    let alpha = |s : &str| s.chars().all(|c| c.is_ascii_alphabetic());
+    let digit = |s : &str| s.chars().all(|c| c.is_ascii_digit());
+    let private = |s : &str| s.eq_ignore_ascii_case("x");
+    if private(subtags[0]) {
+        return subtags.len() > 1;
+    }
+    if subtags[0].len() < 2 || !alpha(subtags[0]) {
+        return false;
+    }
+    let mut i = 1;
+    if subtags[0].len() <= 3 {
+        // Up to three extended language subtags
+        let end = (i + 3).min(subtags.len());
+        while i < end && subtags[i].len() == 3 && alpha(subtags[i]) {
+            i += 1;
+        }
+    }
+    if i < subtags.len() && subtags[i].len() == 4 && alpha(subtags[i]) {
+        i += 1;
+    }
+    if i < subtags.len() && ((subtags[i].len() == 2 && alpha(subtags[i])) || (subtags[i].len() == 3 && digit(subtags[i]))) {
+        i += 1;
+    }
+    while i < subtags.len() && (subtags[i].len() >= 5 ||
+        (subtags[i].len() == 4 && subtags[i].starts_with(|c : char| c.is_ascii_digit()))) {
+        i += 1;
+    }
+    while i < subtags.len() && subtags[i].len() == 1 && !private(subtags[i]) {
+        i += 1;
+        let start = i;
+        while i < subtags.len() && subtags[i].len() >= 2 {
+            i += 1;
+        }
+        if i == start {
+            return false;
+        }
+    }
+    if i < subtags.len() && private(subtags[i]) {
+        return i + 1 < subtags.len();
+    }
+    i == subtags.len()
+}
+
+/// Percent-encode the characters that may not appear in an IRI reference
+fn escape_iri(iri : &str) -> String {
+    let mut s = String::with_capacity(iri.len());
+    for c in iri.chars() {
+        match c {
+            '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\' => s.push_str(&format!("%{:02X}", c as u32)),
+            c if (c as u32) <= 0x20 => s.push_str(&format!("%{:02X}", c as u32)),
+            c => s.push(c)
+        }
+    }
+    s
+}
+
+/// Escape text for XML content or attribute values, dropping characters
+/// that XML 1.0 cannot represent
+pub(crate) fn escape_xml(text : &str, attribute : bool) -> String {
+    let mut s = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => s.push_str("&amp;"),
+            '<' => s.push_str("&lt;"),
+            '>' => s.push_str("&gt;"),
+            '"' if attribute => s.push_str("&quot;"),
+            '\r' => s.push_str("&#13;"),
+            '\n' if attribute => s.push_str("&#10;"),
+            '\t' if attribute => s.push_str("&#9;"),
+            '\n' | '\t' => s.push(c),
+            c if c.is_control() || c == '\u{FFFE}' || c == '\u{FFFF}' => (),
+            c => s.push(c)
+        }
+    }
+    s
+}
+
+/// Whether a string can be the local part of a prefixed name in Turtle
+fn is_turtle_local(local : &str) -> bool {
+    !local.is_empty() &&
+        local.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.') &&
+        !local.starts_with(|c : char| c == '-' || c == '.') &&
+        !local.ends_with('.')
+}
+
+/// Whether a string is a valid XML local name
+fn is_xml_name(local : &str) -> bool {
+    local.starts_with(|c : char| c.is_alphabetic() || c == '_') &&
+        local.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// Percent-encode a string for use as one segment or fragment of an IRI
+pub fn iri_segment(s : &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for c in s.chars() {
+        if c.is_alphanumeric() || "-._~!$&'()*+,;=:@".contains(c) {
+            encoded.push(c);
+        } else {
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                encoded.push_str(&format!("%{:02X}", b));
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hbs::{self, HBSynset};
+    use crate::wordnet::tests::{TAXONOMY, lexicon_from_lmf};
+    use oxigraph::io::{RdfFormat, RdfParser};
+    use oxigraph::model::{Quad, Subject, Term as OxTerm};
+
+    const SKOS : &str = "http://www.w3.org/2004/02/skos/core#";
+    const TEXT : &str = "a \"quoted\" <b> & c\\d\nnaïve 犬\t";
+    const ID : &str = "https://en-word.net/id/";
+
+    fn namespaces() -> Vec<(String, String)> {
+        vec![("skos".to_string(), SKOS.to_string()), ("wnid".to_string(), ID.to_string())]
+    }
+
+    fn graph() -> Graph {
+        let mut graph = Graph::new();
+        let subject = Term::iri(format!("{}a b<c>\"d{{e}}|f^g`h\\i", ID));
+        graph.add(&subject, RDF_TYPE, Term::iri(format!("{}Concept", SKOS)));
+        graph.add(&subject, format!("{}definition", SKOS), Term::lang_literal(TEXT, "en-GB"));
+        graph.add(&subject, format!("{}prefLabel", SKOS), Term::lang_literal("犬", "ja"));
+        graph.add(&subject, format!("{}note", SKOS), Term::literal(TEXT));
+        let example = graph.blank();
+        graph.add(&subject, format!("{}example", SKOS), example.clone());
+        graph.add(&example, format!("{}value", RDF), Term::literal("x & y < z"));
+        graph.add(&Term::iri(format!("{}dog", ID)), format!("{}related", SKOS), subject);
+        graph
+    }
+
+    fn write(mut writer : impl RdfWriter) {
+        writer.write_graph(&graph()).unwrap();
+        writer.finish().unwrap();
+    }
+
+    fn parse(out : &[u8], format : RdfFormat) -> Vec<Quad> {
+        RdfParser::from_format(format).for_reader(out)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|e| panic!("{}\n{}", e, String::from_utf8_lossy(out)))
+    }
+
+    fn check(quads : &[Quad]) {
+        assert_eq!(quads.len(), 7);
+        let iri = format!("{}a%20b%3Cc%3E%22d%7Be%7D%7Cf%5Eg%60h%5Ci", ID);
+        assert_eq!(quads.iter().filter(|q| matches!(&q.subject, Subject::NamedNode(n) if n.as_str() == iri)).count(), 5);
+        assert!(quads.iter().any(|q| matches!(&q.object, OxTerm::NamedNode(n) if n.as_str() == iri)));
+        let literals : Vec<(&str, Option<&str>)> = quads.iter()
+            .filter_map(|q| match &q.object {
+                OxTerm::Literal(l) => Some((l.value(), l.language())),
+                _ => None
+            })
+            .collect();
+        assert!(literals.iter().any(|(v, l)| *v == TEXT && l.is_some_and(|l| l.eq_ignore_ascii_case("en-GB"))));
+        assert!(literals.contains(&("犬", Some("ja"))));
+        assert!(literals.contains(&(TEXT, None)));
+        assert!(literals.contains(&("x & y < z", None)));
+    }
+
+    #[test]
+    fn turtle_round_trip() {
+        let mut out = Vec::new();
+        write(TurtleWriter::new(&mut out, &namespaces()).unwrap());
+        check(&parse(&out, RdfFormat::Turtle));
+    }
+
+    #[test]
+    fn ntriples_round_trip() {
+        let mut out = Vec::new();
+        write(NTriplesWriter::new(&mut out));
+        check(&parse(&out, RdfFormat::NTriples));
+    }
+
+    #[test]
+    fn rdf_xml_round_trip() {
+        let mut out = Vec::new();
+        write(RdfXmlWriter::new(&mut out, &namespaces()).unwrap());
+        check(&parse(&out, RdfFormat::RdfXml));
+    }
+
+    #[test]
+    fn jsonld_terms() {
+        let terms = vec![
+            JsonLdTerm::new("related", format!("{}related", SKOS), JsonLdType::Id),
+            JsonLdTerm::new("definition", format!("{}definition", SKOS), JsonLdType::Language("en".to_string())),
+            JsonLdTerm::new("note", format!("{}note", SKOS), JsonLdType::String),
+        ];
+        let mut out = Vec::new();
+        write(JsonLdWriter::new(&mut out, &namespaces(), &terms).unwrap());
+        let json : Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(json["@context"]["related"], serde_json::json!({"@id": "skos:related", "@type": "@id"}));
+        assert_eq!(json["@context"]["definition"], serde_json::json!({"@id": "skos:definition", "@language": "en"}));
+        let dog = json["@graph"].as_array().unwrap().iter()
+            .find(|n| n["@id"] == "wnid:dog").unwrap();
+        assert!(dog["related"][0].as_str().unwrap().starts_with("wnid:a%20b"));
+        let node = json["@graph"].as_array().unwrap().iter()
+            .find(|n| n.get("note").is_some()).unwrap();
+        assert_eq!(node["note"][0], TEXT);
+        // The definition is in en-GB, so does not fit the term
+        assert!(node.get("definition").is_none());
+        assert_eq!(node["skos:definition"][0]["@language"], "en-GB");
+    }
+
+    /// The graph of each synset of the test taxonomy
+    fn taxonomy() -> Vec<Graph> {
+        let wn = lexicon_from_lmf("rdf", TAXONOMY);
+        wn.synset_ids.iter().map(|id| {
+            let mut graph = Graph::new();
+            HBSynset::from(&wn.synset_by_id(id).unwrap()).add_to_graph(&mut graph);
+            graph
+        }).collect()
+    }
+
+    fn write_all(mut writer : impl RdfWriter, graphs : &[Graph]) {
+        for graph in graphs.iter() {
+            writer.write_graph(graph).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    /// A triple in N-Triples syntax with its blank nodes unlabelled, so that
+    /// the output of different parsers can be compared
+    fn unlabelled(subject : String, predicate : String, object : String) -> (String, String, String) {
+        let blank = |t : String| if t.starts_with("_:") { "_:".to_string() } else { t };
+        (blank(subject), predicate, blank(object))
+    }
+
+    fn sorted(quads : &[Quad]) -> Vec<(String, String, String)> {
+        let mut triples : Vec<_> = quads.iter()
+            .map(|q| unlabelled(q.subject.to_string(), q.predicate.to_string(), q.object.to_string()))
+            .collect();
+        triples.sort();
+        triples
+    }
+
+    #[test]
+    fn taxonomy_round_trip() {
+        let graphs = taxonomy();
+        let mut out = Vec::new();
+        write_all(NTriplesWriter::new(&mut out), &graphs);
+        let quads = parse(&out, RdfFormat::NTriples);
+        assert_eq!(quads.len(), graphs.iter().map(|g| g.triples.len()).sum::<usize>());
+        assert!(quads.iter().any(|q| matches!(&q.object, OxTerm::Literal(l)
+            if l.value() == "a \"domestic\" <canine> & pet,\nin Welsh «ci»" && l.language() == Some("en"))));
+        assert!(quads.iter().any(|q| matches!(&q.object, OxTerm::Literal(l) if l.value() == "the dog barked\ntwice")));
+        let expected = sorted(&quads);
+
+        let mut out = Vec::new();
+        write_all(TurtleWriter::new(&mut out, &hbs::namespaces()).unwrap(), &graphs);
+        assert_eq!(sorted(&parse(&out, RdfFormat::Turtle)), expected);
+
+        let mut out = Vec::new();
+        write_all(RdfXmlWriter::new(&mut out, &hbs::namespaces()).unwrap(), &graphs);
+        assert_eq!(sorted(&parse(&out, RdfFormat::RdfXml)), expected);
+
+        let mut out = Vec::new();
+        write_all(JsonLdWriter::new(&mut out, &hbs::namespaces(), &hbs::jsonld_terms()).unwrap(), &graphs);
+        let mut jsonld : Vec<_> = oxjsonld::JsonLdParser::new().for_reader(out.as_slice())
+            .map(|q| q.unwrap_or_else(|e| panic!("{}\n{}", e, String::from_utf8_lossy(&out))))
+            .map(|q| unlabelled(q.subject.to_string(), q.predicate.to_string(), q.object.to_string()))
+            .collect();
+        jsonld.sort();
+        assert_eq!(jsonld, expected);
+    }
+
+    #[test]
+    fn invalid_language_tag() {
+        let mut graph = Graph::new();
+        graph.add(&Term::iri(ID), format!("{}prefLabel", SKOS), Term::lang_literal("x", "en gb"));
+        let mut out = Vec::new();
+        assert!(NTriplesWriter::new(&mut out).write_graph(&graph).is_err());
+        assert!(TurtleWriter::new(&mut out, &[]).unwrap().write_graph(&graph).is_err());
+        assert!(RdfXmlWriter::new(&mut out, &[]).unwrap().write_graph(&graph).is_err());
+        assert!(JsonLdWriter::new(&mut out, &[], &[]).unwrap().write_graph(&graph).is_err());
+    }
+
+    #[test]
+    fn language_tags() {
+        for tag in ["en", "en-GB", "zh-Hant-TW", "zh-yue", "es-419", "sl-rozaj-biske", "de-CH-1901",
+            "en-a-bbb-x-a-ccc", "x-whatever", "cmn"] {
+            assert!(is_language_tag(tag), "{}", tag);
+        }
+        for tag in ["", "e", "en_GB", "en-", "-en", "en--GB", "1en", "en-a", "en-x", "toolongtag", "en-GB-a-", "ja jp"] {
+            assert!(!is_language_tag(tag), "{}", tag);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+/// Full-text search over definitions and examples
+///
+
+use crate::wordnet::{SynsetId, MemberSynset};
+use serde::Serialize;
+use speedy::{Readable, Writable};
+use std::collections::{HashMap, HashSet};
+
+const BM25_K1 : f64 = 1.2;
+const BM25_B : f64 = 0.75;
+
+/// An inverted index from the words of each synset's definitions and
+/// examples to the synsets, ranked with BM25
+#[derive(Debug, Default, Readable, Writable)]
+pub struct TextIndex {
+    postings : HashMap<String, Vec<(u32, u32)>>,
+    lengths : Vec<u32>,
+    synsets : Vec<SynsetId>,
+}
+
+impl TextIndex {
+    /// Index the definitions and examples of the given synsets
+    pub fn build<'a, I : Iterator<Item=&'a MemberSynset>>(synsets : I) -> TextIndex {
+        let mut index = TextIndex::default();
+        for synset in synsets {
+            let doc = index.synsets.len() as u32;
+            let mut counts : HashMap<String, u32> = HashMap::new();
+            let mut length = 0;
+            for text in searchable_texts(synset) {
+                for (_, _, token) in tokenize(&text) {
+                    *counts.entry(token).or_insert(0) += 1;
+                    length += 1;
+                }
+            }
+            for (token, count) in counts {
+                index.postings.entry(token).or_insert_with(Vec::new).push((doc, count));
+            }
+            index.lengths.push(length);
+            index.synsets.push(synset.id.clone());
+        }
+        index
+    }
+
+    /// Find the `limit` best matching synsets for a query
+    pub fn search(&self, query : &str, limit : usize) -> Vec<(SynsetId, f64)> {
+        if self.synsets.is_empty() {
+            return Vec::new();
+        }
+        let n = self.synsets.len() as f64;
+        let avg_length = self.lengths.iter().map(|x| *x as f64).sum::<f64>() / n;
+        let terms : HashSet<String> = tokenize(query).into_iter().map(|(_, _, t)| t).collect();
+        let mut scores : HashMap<u32, f64> = HashMap::new();
+        for term in terms.iter() {
+            if let Some(postings) = self.postings.get(term) {
+                let df = postings.len() as f64;
+                let idf = (1.0 + (n - df + 0.5) / (df + 0.5)).ln();
+                for (doc, tf) in postings.iter() {
+                    let tf = *tf as f64;
+                    let length = self.lengths[*doc as usize] as f64;
+                    let score = idf * tf * (BM25_K1 + 1.0) /
+                        (tf + BM25_K1 * (1.0 - BM25_B + BM25_B * length / avg_length));
+                    *scores.entry(*doc).or_insert(0.0) += score;
+                }
+            }
+        }
+        let mut results : Vec<(u32, f64)> = scores.into_iter().collect();
+        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0)));
+        results.into_iter()
+            .take(limit)
+            .map(|(doc, score)| (self.synsets[doc as usize].clone(), score))
+            .collect()
+    }
+}
+
+/// A definition or example with the offsets of the query terms
+#[derive(Debug, Serialize)]
+pub struct Snippet {
+    pub text : String,
+    /// The start and end of each query term in UTF-16 code units, as used by
+    /// JavaScript's `String.substring`
+    pub highlights : Vec<(usize, usize)>,
+}
+
+/// Choose the definition or example that best matches a query
+pub fn snippet(synset : &MemberSynset, query : &str) -> Snippet {
+    let terms : HashSet<String> = tokenize(query).into_iter().map(|(_, _, t)| t).collect();
+    let mut best : Option<Snippet> = None;
+    for text in searchable_texts(synset) {
+        let highlights : Vec<(usize, usize)> = tokenize(&text).into_iter()
+            .filter(|(_, _, t)| terms.contains(t))
+            .map(|(start, end, _)| (start, end))
+            .collect();
+        if best.as_ref().map(|b| highlights.len() > b.highlights.len()).unwrap_or(true) {
+            best = Some(Snippet { text, highlights });
+        }
+    }
+    best.unwrap_or_else(|| Snippet { text: String::new(), highlights: Vec::new() })
+}
+
+fn searchable_texts(synset : &MemberSynset) -> Vec<String> {
+    synset.definition.iter().cloned()
+        .chain(synset.example.iter().map(|e| e.text.clone()))
+        .collect()
+}
+
+/// Split a text into lower case words with their start and end offsets in
+/// UTF-16 code units
+fn tokenize(text : &str) -> Vec<(usize, usize, String)> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut token = String::new();
+    let mut offset = 0;
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            if start.is_none() {
+                start = Some(offset);
+            }
+            token.extend(c.to_lowercase());
+        } else if let Some(s) = start.take() {
+            tokens.push((s, offset, std::mem::take(&mut token)));
+        }
+        offset += c.len_utf16();
+    }
+    if let Some(s) = start {
+        tokens.push((s, offset, token));
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf16_offsets() {
+        let tokens = tokenize("𝔸 café, Ünïcode");
+        assert_eq!(tokens, vec![
+            (0, 2, "𝔸".to_string()),
+            (3, 7, "café".to_string()),
+            (9, 16, "ünïcode".to_string())]);
+        let text : Vec<u16> = "𝔸 café, Ünïcode".encode_utf16().collect();
+        assert_eq!(String::from_utf16(&text[9..16]).unwrap(), "Ünïcode");
+    }
+}
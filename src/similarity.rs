@@ -0,0 +1,261 @@
+/// Semantic similarity measures over the hypernym hierarchy
+///
+
+use crate::wordnet::{Lexicon, SynsetId};
+use std::collections::{HashMap, HashSet};
+
+/// The similarity measures offered through the API
+pub enum Measure { Path, Lch, Wup }
+
+impl Measure {
+    pub fn from_str(s : &str) -> Option<Measure> {
+        match s {
+            "path" => Some(Measure::Path),
+            "lch" => Some(Measure::Lch),
+            "wup" => Some(Measure::Wup),
+            _ => None
+        }
+    }
+}
+
+impl Lexicon {
+    /// Compute a similarity measure between two synsets
+    pub fn similarity(&self, measure : &Measure, a : &SynsetId, b : &SynsetId) -> Option<f64> {
+        match measure {
+            Measure::Path => self.path_similarity(a, b),
+            Measure::Lch => self.lch_similarity(a, b),
+            Measure::Wup => self.wup_similarity(a, b),
+        }
+    }
+
+    /// The inverse of one plus the length of the shortest hypernym path
+    /// between two synsets
+    pub fn path_similarity(&self, a : &SynsetId, b : &SynsetId) -> Option<f64> {
+        self.shortest_path_distance(a, b).map(|d| 1.0 / (d as f64 + 1.0))
+    }
+
+    /// Leacock-Chodorow similarity, `-log((d + 1) / 2D)` where `D` is the
+    /// number of hypernym links on the longest path in the taxonomy, plus
+    /// one for the root assumed above verbs, as in NLTK. Only defined for
+    /// synsets of the same part of speech
+    pub fn lch_similarity(&self, a : &SynsetId, b : &SynsetId) -> Option<f64> {
+        let pos = pos_group(a);
+        if pos != pos_group(b) {
+            return None;
+        }
+        let mut depth = *self.taxonomy_depths().get(&pos)? as f64;
+        if simulate_root(a, b) {
+            depth += 1.0;
+        }
+        if depth == 0.0 {
+            return None;
+        }
+        let distance = self.shortest_path_distance(a, b)? as f64;
+        Some(-((distance + 1.0) / (2.0 * depth)).ln())
+    }
+
+    /// Wu-Palmer similarity, `2 * depth(lcs) / (len(a, lcs) + len(b, lcs) + 2 * depth(lcs))`.
+    /// As in NLTK, the subsumer is chosen by its shortest path to a root
+    /// and `depth(lcs)` is one more than its longest
+    pub fn wup_similarity(&self, a : &SynsetId, b : &SynsetId) -> Option<f64> {
+        let da = self.ancestor_distances(a);
+        let db = self.ancestor_distances(b);
+        let (depth, len_a, len_b) = match self.common_subsumers(a, b, true).first() {
+            Some(lcs) => (self.max_depth(lcs) + 1, da[lcs], db[lcs]),
+            None if simulate_root(a, b) => (1, self.root_distance(a) + 1, self.root_distance(b) + 1),
+            None => return None
+        };
+        Some(2.0 * depth as f64 / ((len_a + len_b + 2 * depth) as f64))
+    }
+
+    /// The deepest synsets that are (instance) hypernyms of both synsets,
+    /// including the synsets themselves
+    pub fn lowest_common_subsumers(&self, a : &SynsetId, b : &SynsetId) -> Vec<SynsetId> {
+        self.common_subsumers(a, b, false)
+    }
+
+    /// The common subsumers that are deepest by their longest path to a
+    /// root, or by their shortest if `use_min_depth` is set
+    fn common_subsumers(&self, a : &SynsetId, b : &SynsetId, use_min_depth : bool) -> Vec<SynsetId> {
+        let da = self.ancestor_distances(a);
+        let db = self.ancestor_distances(b);
+        // The common subsumers share their ancestors, so the depths are
+        // computed together
+        let mut memo = HashMap::new();
+        let common : Vec<(SynsetId, usize)> = da.keys()
+            .filter(|s| db.contains_key(*s))
+            .map(|s| {
+                let (min, max) = self.depths_memo(s, &mut memo, &mut HashSet::new());
+                (s.clone(), if use_min_depth { min } else { max })
+            })
+            .collect();
+        let deepest = common.iter().map(|(_, d)| *d).max();
+        let mut lcs : Vec<SynsetId> = common.into_iter()
+            .filter(|(_, d)| Some(*d) == deepest)
+            .map(|(s, _)| s)
+            .collect();
+        lcs.sort();
+        lcs
+    }
+
+    /// The number of hypernym links on the shortest path between two
+    /// synsets. Verbs have no single root, so a root above all verbs is
+    /// assumed
+    pub fn shortest_path_distance(&self, a : &SynsetId, b : &SynsetId) -> Option<usize> {
+        let da = self.ancestor_distances(a);
+        let db = self.ancestor_distances(b);
+        let root = if simulate_root(a, b) {
+            Some(self.root_distance(a) + self.root_distance(b) + 2)
+        } else {
+            None
+        };
+        da.iter()
+            .filter_map(|(s, x)| db.get(s).map(|y| x + y))
+            .chain(root)
+            .min()
+    }
+
+    /// The length of the longest hypernym path from a synset to a root
+    pub fn max_depth(&self, synset_id : &SynsetId) -> usize {
+        self.depths_memo(synset_id, &mut HashMap::new(), &mut HashSet::new()).1
+    }
+
+    /// The length of the shortest hypernym path from a synset to a root
+    fn root_distance(&self, synset_id : &SynsetId) -> usize {
+        self.depths_memo(synset_id, &mut HashMap::new(), &mut HashSet::new()).0
+    }
+
+    fn ancestor_distances(&self, synset_id : &SynsetId) -> HashMap<SynsetId, usize> {
+        let mut distances : HashMap<SynsetId, usize> = self.ancestors(synset_id).into_iter().collect();
+        distances.insert(synset_id.clone(), 0);
+        distances
+    }
+
+    /// The maximum depth of the taxonomy for each part of speech, which is
+    /// computed on first use
+    fn taxonomy_depths(&self) -> &HashMap<char, usize> {
+        self.depth_cache.get_or_init(|| {
+            let mut memo = HashMap::new();
+            let mut depths = HashMap::new();
+            for ssid in self.synset_ids.iter() {
+                let (_, depth) = self.depths_memo(ssid, &mut memo, &mut HashSet::new());
+                let max = depths.entry(pos_group(ssid)).or_insert(0);
+                *max = depth.max(*max);
+            }
+            depths
+        })
+    }
+
+    /// The lengths of the shortest and the longest hypernym paths from a
+    /// synset to a root, memoized for the synset and its ancestors
+    fn depths_memo(&self, synset_id : &SynsetId, memo : &mut HashMap<SynsetId, (usize, usize)>,
+        stack : &mut HashSet<SynsetId>) -> (usize, usize) {
+        if let Some(depths) = memo.get(synset_id) {
+            return *depths;
+        }
+        stack.insert(synset_id.clone());
+        let mut depths : Option<(usize, usize)> = None;
+        for h in self.hypernyms(synset_id) {
+            // Guard against cycles in the source data
+            if !stack.contains(&h) {
+                let (min, max) = self.depths_memo(&h, memo, stack);
+                depths = Some(match depths {
+                    Some((a, b)) => (a.min(min + 1), b.max(max + 1)),
+                    None => (min + 1, max + 1)
+                });
+            }
+        }
+        stack.remove(synset_id);
+        let depths = depths.unwrap_or((0, 0));
+        memo.insert(synset_id.clone(), depths);
+        depths
+    }
+}
+
+/// The part of speech of a synset from its ID, with satellites counted as
+/// adjectives
+fn pos_group(synset_id : &SynsetId) -> char {
+    match synset_id.as_str().chars().last() {
+        Some('s') => 'a',
+        Some(c) => c,
+        None => ' '
+    }
+}
+
+fn simulate_root(a : &SynsetId, b : &SynsetId) -> bool {
+    pos_group(a) == 'v' && pos_group(b) == 'v'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wordnet::tests::{TAXONOMY, lexicon_from_lmf};
+
+    const ENTITY : &str = "00000001-n";
+    const ANIMAL : &str = "00000002-n";
+    const DOG : &str = "00000003-n";
+    const CAT : &str = "00000004-n";
+    const PUPPY : &str = "00000005-n";
+    const RUN : &str = "00000006-v";
+    const JOG : &str = "00000007-v";
+    const EAT : &str = "00000008-v";
+
+    fn id(s : &str) -> SynsetId {
+        SynsetId::new(s)
+    }
+
+    fn assert_close(value : Option<f64>, expected : f64) {
+        let value = value.unwrap();
+        assert!((value - expected).abs() < 1e-4, "{} != {}", value, expected);
+    }
+
+    // The expected values are those of NLTK's path_similarity,
+    // lch_similarity and wup_similarity for the same taxonomy, in which
+    // the longest noun path has 3 links and the longest verb path 1. In
+    // WordNet 3.0 the noun taxonomy is 19 links deep, so NLTK gives
+    // ln(2 * 19) = 3.6376 for the LCH similarity of a noun with itself.
+
+    #[test]
+    fn path() {
+        let wn = lexicon_from_lmf("path", TAXONOMY);
+        assert_close(wn.path_similarity(&id(DOG), &id(DOG)), 1.0);
+        assert_close(wn.path_similarity(&id(DOG), &id(CAT)), 1.0 / 3.0);
+        assert_close(wn.path_similarity(&id(PUPPY), &id(CAT)), 0.25);
+        assert_close(wn.path_similarity(&id(PUPPY), &id(ENTITY)), 0.25);
+        assert_close(wn.path_similarity(&id(JOG), &id(EAT)), 0.25);
+        assert_close(wn.path_similarity(&id(RUN), &id(EAT)), 1.0 / 3.0);
+        assert_eq!(wn.path_similarity(&id(DOG), &id(RUN)), None);
+    }
+
+    #[test]
+    fn lch() {
+        let wn = lexicon_from_lmf("lch", TAXONOMY);
+        assert_close(wn.lch_similarity(&id(DOG), &id(DOG)), 6f64.ln());
+        assert_close(wn.lch_similarity(&id(DOG), &id(CAT)), 2f64.ln());
+        assert_close(wn.lch_similarity(&id(PUPPY), &id(CAT)), 0.4055);
+        assert_close(wn.lch_similarity(&id(RUN), &id(JOG)), 2f64.ln());
+        assert_close(wn.lch_similarity(&id(JOG), &id(EAT)), 0.0);
+        assert_eq!(wn.lch_similarity(&id(DOG), &id(RUN)), None);
+    }
+
+    #[test]
+    fn wup() {
+        let wn = lexicon_from_lmf("wup", TAXONOMY);
+        assert_close(wn.wup_similarity(&id(DOG), &id(DOG)), 1.0);
+        assert_close(wn.wup_similarity(&id(DOG), &id(CAT)), 2.0 / 3.0);
+        assert_close(wn.wup_similarity(&id(PUPPY), &id(CAT)), 4.0 / 7.0);
+        assert_close(wn.wup_similarity(&id(RUN), &id(JOG)), 2.0 / 3.0);
+        assert_close(wn.wup_similarity(&id(JOG), &id(EAT)), 0.4);
+        assert_eq!(wn.wup_similarity(&id(DOG), &id(RUN)), None);
+    }
+
+    #[test]
+    fn subsumers() {
+        let wn = lexicon_from_lmf("lcs", TAXONOMY);
+        assert_eq!(wn.lowest_common_subsumers(&id(PUPPY), &id(CAT)), vec![id(ANIMAL)]);
+        assert_eq!(wn.lowest_common_subsumers(&id(PUPPY), &id(DOG)), vec![id(DOG)]);
+        assert!(wn.lowest_common_subsumers(&id(JOG), &id(EAT)).is_empty());
+        assert_eq!(wn.max_depth(&id(PUPPY)), 3);
+        assert_eq!(wn.max_depth(&id(EAT)), 0);
+    }
+}
@@ -0,0 +1,246 @@
+/// SPARQL 1.1 Protocol over an in-memory triple store
+///
+
+use oxigraph::io::{RdfFormat, RdfParser, RdfSerializer};
+use oxigraph::sparql::{Query, QueryResults, QueryResultsFormat};
+use oxigraph::sparql::results::QueryResultsSerializer;
+use oxigraph::store::Store;
+use rocket::request::{FromRequest, Request, Outcome};
+use rocket::tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// How long a query may run for
+pub const QUERY_TIMEOUT : Duration = Duration::from_secs(30);
+/// The most solutions or triples returned for a query; any more are
+/// dropped
+pub const MAX_RESULTS : usize = 10_000;
+/// The most queries evaluated at once. A query that has timed out still
+/// counts until its evaluation stops
+pub const MAX_QUERIES : usize = 4;
+
+/// The triple store holding the RDF view of the lexicon
+pub struct SparqlStore {
+    store : Store,
+    queries : Arc<Semaphore>,
+}
+
+impl SparqlStore {
+    /// Load an N-Triples document
+    pub fn load(ntriples : &[u8]) -> Result<SparqlStore, SparqlError> {
+        let store = Store::new()
+            .map_err(|e| SparqlError::Load(e.to_string()))?;
+        store.load_from_read(RdfParser::from_format(RdfFormat::NTriples), ntriples)
+            .map_err(|e| SparqlError::Load(e.to_string()))?;
+        Ok(SparqlStore { store, queries: Arc::new(Semaphore::new(MAX_QUERIES)) })
+    }
+
+    /// Reserve one of the `MAX_QUERIES` evaluations. The permit should be
+    /// held until the query has finished, not just until the request has
+    /// been answered
+    pub fn permit(&self) -> Result<OwnedSemaphorePermit, SparqlError> {
+        self.queries.clone().try_acquire_owned()
+            .map_err(|_| SparqlError::Busy)
+    }
+
+    /// Evaluate a query, returning the media type and the serialized
+    /// results in the format preferred by the client. At most `MAX_RESULTS`
+    /// results are returned, and the query fails if they are still being
+    /// produced at the deadline
+    pub fn query(&self, query : &str, accept : &SparqlAccept, deadline : Instant) -> Result<(&'static str, Vec<u8>), SparqlError> {
+        let query = Query::parse(query, None)
+            .map_err(|e| SparqlError::Syntax(e.to_string()))?;
+        let results = self.store.query(query)
+            .map_err(|e| SparqlError::Evaluation(e.to_string()))?;
+        let error = |e : std::io::Error| SparqlError::Evaluation(e.to_string());
+        match results {
+            QueryResults::Boolean(_) => {
+                let format = accept.results_format();
+                let data = results.write(Vec::new(), format)
+                    .map_err(|e| SparqlError::Evaluation(e.to_string()))?;
+                Ok((format.media_type(), data))
+            },
+            QueryResults::Solutions(solutions) => {
+                let format = accept.results_format();
+                let mut writer = QueryResultsSerializer::from_format(format)
+                    .serialize_solutions_to_writer(Vec::new(), solutions.variables().to_vec())
+                    .map_err(error)?;
+                for solution in solutions.take(MAX_RESULTS) {
+                    check_deadline(deadline)?;
+                    let solution = solution.map_err(|e| SparqlError::Evaluation(e.to_string()))?;
+                    writer.serialize(&solution).map_err(error)?;
+                }
+                Ok((format.media_type(), writer.finish().map_err(error)?))
+            },
+            QueryResults::Graph(triples) => {
+                let format = accept.graph_format();
+                let mut writer = RdfSerializer::from_format(format).for_writer(Vec::new());
+                for triple in triples.take(MAX_RESULTS) {
+                    check_deadline(deadline)?;
+                    let triple = triple.map_err(|e| SparqlError::Evaluation(e.to_string()))?;
+                    writer.serialize_triple(&triple).map_err(error)?;
+                }
+                Ok((format.media_type(), writer.finish().map_err(error)?))
+            }
+        }
+    }
+}
+
+fn check_deadline(deadline : Instant) -> Result<(), SparqlError> {
+    if Instant::now() > deadline {
+        Err(SparqlError::Timeout)
+    } else {
+        Ok(())
+    }
+}
+
+/// The media types listed in the Accept header of a SPARQL request
+pub struct SparqlAccept(Vec<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for SparqlAccept {
+    type Error = String;
+    async fn from_request(request: &'r Request<'_>) -> Outcome<SparqlAccept, String> {
+        let mut types = Vec::new();
+        for value in request.headers().get("Accept") {
+            types.extend(value.split(',').map(|t| t.trim().to_string()));
+        }
+        Outcome::Success(SparqlAccept(types))
+    }
+}
+
+impl SparqlAccept {
+    /// The format for SELECT and ASK queries, JSON by default
+    fn results_format(&self) -> QueryResultsFormat {
+        for value in self.0.iter() {
+            if value.starts_with("application/sparql-results+json") || value.starts_with("application/json") {
+                return QueryResultsFormat::Json;
+            } else if value.starts_with("application/sparql-results+xml") || value.starts_with("application/xml") {
+                return QueryResultsFormat::Xml;
+            } else if value.starts_with("text/csv") {
+                return QueryResultsFormat::Csv;
+            } else if value.starts_with("text/tab-separated-values") {
+                return QueryResultsFormat::Tsv;
+            }
+        }
+        QueryResultsFormat::Json
+    }
+
+    /// The format for CONSTRUCT and DESCRIBE queries, Turtle by default
+    fn graph_format(&self) -> RdfFormat {
+        for value in self.0.iter() {
+            if value.starts_with("text/turtle") || value.starts_with("application/x-turtle") {
+                return RdfFormat::Turtle;
+            } else if value.starts_with("application/n-triples") {
+                return RdfFormat::NTriples;
+            } else if value.starts_with("application/rdf+xml") {
+                return RdfFormat::RdfXml;
+            }
+        }
+        RdfFormat::Turtle
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SparqlError {
+    #[error("Failed to load the triple store: {0}")]
+    Load(String),
+    #[error("Invalid SPARQL query: {0}")]
+    Syntax(String),
+    #[error("Failed to evaluate query: {0}")]
+    Evaluation(String),
+    #[error("The query took longer than {} seconds", QUERY_TIMEOUT.as_secs())]
+    Timeout,
+    #[error("Too many queries are running, try again later")]
+    Busy,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    const DATA : &str = "<http://example.org/dog> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/Animal> .
+<http://example.org/dog> <http://www.w3.org/2000/01/rdf-schema#label> \"dog\"@en .
+<http://example.org/cat> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/Animal> .
+<http://example.org/cat> <http://www.w3.org/2000/01/rdf-schema#label> \"cat\"@en .
+";
+
+    fn store() -> SparqlStore {
+        SparqlStore::load(DATA.as_bytes()).unwrap()
+    }
+
+    fn accept(types : &[&str]) -> SparqlAccept {
+        SparqlAccept(types.iter().map(|t| t.to_string()).collect())
+    }
+
+    fn deadline() -> Instant {
+        Instant::now() + QUERY_TIMEOUT
+    }
+
+    const SELECT : &str = "SELECT ?label WHERE { ?x a <http://example.org/Animal> ; <http://www.w3.org/2000/01/rdf-schema#label> ?label } ORDER BY ?label";
+
+    #[test]
+    fn select() {
+        let (media_type, data) = store().query(SELECT, &accept(&[]), deadline()).unwrap();
+        assert_eq!(media_type, "application/sparql-results+json");
+        let json : Value = serde_json::from_slice(&data).unwrap();
+        assert_eq!(json["head"]["vars"], serde_json::json!(["label"]));
+        let labels : Vec<&Value> = json["results"]["bindings"].as_array().unwrap().iter()
+            .map(|b| &b["label"]["value"]).collect();
+        assert_eq!(labels, vec!["cat", "dog"]);
+    }
+
+    #[test]
+    fn ask() {
+        let (media_type, data) = store().query("ASK { <http://example.org/dog> a <http://example.org/Animal> }", &accept(&[]), deadline()).unwrap();
+        assert_eq!(media_type, "application/sparql-results+json");
+        let json : Value = serde_json::from_slice(&data).unwrap();
+        assert_eq!(json["boolean"], true);
+        let (media_type, data) = store().query("ASK { <http://example.org/cat> a <http://example.org/Plant> }",
+            &accept(&["application/sparql-results+xml"]), deadline()).unwrap();
+        assert_eq!(media_type, "application/sparql-results+xml");
+        assert!(String::from_utf8(data).unwrap().contains("<boolean>false</boolean>"));
+    }
+
+    #[test]
+    fn construct() {
+        let query = "CONSTRUCT { ?x <http://example.org/name> ?label } WHERE { ?x <http://www.w3.org/2000/01/rdf-schema#label> ?label }";
+        let (media_type, data) = store().query(query, &accept(&["application/n-triples"]), deadline()).unwrap();
+        assert_eq!(media_type, "application/n-triples");
+        let triples = RdfParser::from_format(RdfFormat::NTriples).for_reader(data.as_slice())
+            .collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(triples.len(), 2);
+        assert!(triples.iter().all(|t| t.predicate.as_str() == "http://example.org/name"));
+        let (media_type, _) = store().query(query, &accept(&[]), deadline()).unwrap();
+        assert_eq!(media_type, "text/turtle");
+    }
+
+    #[test]
+    fn accept_formats() {
+        let (media_type, data) = store().query(SELECT, &accept(&["text/html", "text/csv;q=0.9"]), deadline()).unwrap();
+        assert_eq!(media_type, "text/csv");
+        assert_eq!(String::from_utf8(data).unwrap(), "label\r\ncat\r\ndog\r\n");
+        let (media_type, _) = store().query(SELECT, &accept(&["text/tab-separated-values"]), deadline()).unwrap();
+        assert_eq!(media_type, "text/tab-separated-values");
+        let (media_type, _) = store().query(SELECT, &accept(&["application/sparql-results+xml"]), deadline()).unwrap();
+        assert_eq!(media_type, "application/sparql-results+xml");
+        let (media_type, _) = store().query("DESCRIBE <http://example.org/dog>", &accept(&["application/rdf+xml"]), deadline()).unwrap();
+        assert_eq!(media_type, "application/rdf+xml");
+    }
+
+    #[test]
+    fn invalid_query() {
+        assert!(matches!(store().query("SELECT WHERE", &accept(&[]), deadline()), Err(SparqlError::Syntax(_))));
+    }
+
+    #[test]
+    fn permits() {
+        let store = store();
+        let permits : Vec<_> = (0..MAX_QUERIES).map(|_| store.permit().unwrap()).collect();
+        assert!(matches!(store.permit(), Err(SparqlError::Busy)));
+        drop(permits);
+        assert!(store.permit().is_ok());
+    }
+}
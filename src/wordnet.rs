@@ -1,61 +1,142 @@
 use thiserror::Error;
 use serde::{Serialize,Deserialize,Serializer,Deserializer};
-use std::collections::{HashMap, BTreeMap};
+use std::collections::{HashMap, HashSet, BTreeMap};
 use std::fs;
 use std::path::Path;
 use std::fs::File;
+use std::io::BufReader;
 use std::fmt;
 use serde::de::{self, Visitor, MapAccess};
 use serde::ser::SerializeMap;
 use indicatif::ProgressBar;
-use redb::{TableDefinition, TypeName, Database};
+use redb::{TableDefinition, TypeName, Database, ReadableTable};
 use speedy::{Readable, Writable};
+use crate::search::TextIndex;
+use crate::pwn::{PwnVersion, offset_key};
+use crate::lmf::{LmfData, LmfError, read_lmf, sense_key};
+use flate2::read::GzDecoder;
+use once_cell::sync::OnceCell;
 
 
 const TABLE: TableDefinition<&str, MemberSynset> = TableDefinition::new("lexicon");
 
+/// The version of the layout of `wordnet.db` and `wordnet.data`. Increase
+/// this whenever `MemberSynset` or `LexiconData` change.
+///
+/// Each edition of the wordnet has its own cache files, named after the
+/// `cache` argument of the functions that read or write them: the main
+/// edition uses `wordnet`, other editions `wordnet-<edition>`.
+const FORMAT_VERSION: u32 = 10;
+
+/// The indexes stored in `wordnet.data`: entries, ILIs, synset IDs, the
+/// content hash of each source file, the lemmas of irregular forms, the
+/// subcategorization frames, the synsets linked to each Wikidata item, the
+/// synsets proposed for a new ILI, the synset of each sense and the synsets
+/// whose ILI is not a valid identifier
+type LexiconData = (HashMap<String, Vec<SynsetId>>, HashMap<String, SynsetId>, Vec<SynsetId>,
+    HashMap<String, u64>, HashMap<String, Vec<String>>, HashMap<String, String>,
+    HashMap<String, Vec<SynsetId>>, Vec<SynsetId>, HashMap<String, SynsetId>, Vec<SynsetId>);
+
+/// WordNet's detachment rules: an inflectional suffix and its replacement
+const NOUN_RULES : &[(&str, &str)] = &[
+    ("s", ""), ("ses", "s"), ("xes", "x"), ("zes", "z"), ("ches", "ch"),
+    ("shes", "sh"), ("men", "man"), ("ies", "y")];
+const VERB_RULES : &[(&str, &str)] = &[
+    ("s", ""), ("ies", "y"), ("es", "e"), ("es", ""), ("ed", "e"), ("ed", ""),
+    ("ing", "e"), ("ing", "")];
+const ADJ_RULES : &[(&str, &str)] = &[
+    ("er", ""), ("est", ""), ("er", "e"), ("est", "e")];
+
 /// The Lexicon contains the whole WordNet graph
 pub struct Lexicon {
     //pub synsets : HashMap<SynsetId, MemberSynset>,
     db: redb::Database,
     entries : HashMap<String, Vec<SynsetId>>,
     synsets_by_ili : HashMap<String, SynsetId>,
-    pub synset_ids : Vec<SynsetId>
+    pub synset_ids : Vec<SynsetId>,
+    lemma_index : PrefixIndex,
+    ssid_index : PrefixIndex,
+    ili_index : PrefixIndex,
+    text_index : TextIndex,
+    forms : HashMap<String, Vec<String>>,
+    frames : HashMap<String, String>,
+    wikidata : HashMap<String, Vec<SynsetId>>,
+    new_ili : Vec<SynsetId>,
+    senses : HashMap<String, SynsetId>,
+    invalid_ili : Vec<SynsetId>,
+    pwn_offsets : HashMap<PwnVersion, HashMap<String, Vec<SynsetId>>>,
+    pub(crate) depth_cache : OnceCell<HashMap<char, usize>>,
 }
 
 impl Lexicon {
     /// Create a new empty lexicon
     #[allow(dead_code)]
     pub fn new() -> Lexicon {
-        Lexicon {
-            entries: HashMap::new(),
-            //synsets: HashMap::new(),
-            db: Database::create("wordnet.db").unwrap(),
-            synsets_by_ili: HashMap::new(),
-            synset_ids : Vec::new()
-        }
+        Lexicon::with_indexes(Database::create("wordnet.db").unwrap(),
+            HashMap::new(), HashMap::new(), Vec::new(), TextIndex::default(), HashMap::new(), HashMap::new(),
+            HashMap::new(), Vec::new(), HashMap::new(), Vec::new())
     }
 
-    pub fn from_disk() -> Lexicon {
-        let db = Database::open("wordnet.db").unwrap();
-        let file = File::open("wordnet.data").unwrap();
-        let (entries, synsets_by_ili, synset_ids) = 
-            <(HashMap::<String, Vec<SynsetId>>, HashMap::<String, SynsetId>, Vec::<SynsetId>)>::read_from_stream_buffered(&file).unwrap();
+    /// Create a lexicon and build its prefix indexes
+    #[allow(clippy::too_many_arguments)]
+    fn with_indexes(db : Database, entries : HashMap<String, Vec<SynsetId>>,
+        synsets_by_ili : HashMap<String, SynsetId>, synset_ids : Vec<SynsetId>,
+        text_index : TextIndex, forms : HashMap<String, Vec<String>>,
+        frames : HashMap<String, String>, wikidata : HashMap<String, Vec<SynsetId>>,
+        new_ili : Vec<SynsetId>, senses : HashMap<String, SynsetId>,
+        invalid_ili : Vec<SynsetId>) -> Lexicon {
+        let lemma_index = PrefixIndex::new(entries.keys().map(|k| k.as_str()));
+        let ssid_index = PrefixIndex::new(synset_ids.iter().map(|k| k.0.as_str()));
+        let ili_index = PrefixIndex::new(synsets_by_ili.keys().map(|k| k.as_str()));
         Lexicon {
             db,
             entries,
             synsets_by_ili,
-            synset_ids
+            synset_ids,
+            lemma_index,
+            ssid_index,
+            ili_index,
+            text_index,
+            forms,
+            frames,
+            wikidata,
+            new_ili,
+            senses,
+            invalid_ili,
+            pwn_offsets: HashMap::new(),
+            depth_cache: OnceCell::new()
+        }
+    }
+
+    /// Open the lexicon cached in `<cache>.db` and `<cache>.data`. If a
+    /// source folder or WN-LMF file is given, the cache must have been built
+    /// from it
+    pub fn from_disk(source : Option<&Path>, cache : &str) -> Result<Lexicon, WordNetCacheError> {
+        let (fingerprint, (entries, synsets_by_ili, synset_ids, _, forms, frames, wikidata, new_ili, senses, invalid_ili)) = read_cache(cache)?;
+        if let Some(source) = source {
+            let hashes = source_hashes(source)
+                .map_err(|e| WordNetCacheError::Source(e.to_string()))?;
+            if source_fingerprint(&hashes) != fingerprint {
+                return Err(WordNetCacheError::Stale);
+            }
         }
+        let search_file = File::open(format!("{}.search", cache))
+            .map_err(|e| WordNetCacheError::Missing(format!("{}.search: {}", cache, e)))?;
+        let text_index = TextIndex::read_from_stream_buffered(&search_file)
+            .map_err(|e| WordNetCacheError::Corrupt(format!("{}.search: {}", cache, e)))?;
+        let db = Database::open(format!("{}.db", cache))
+            .map_err(|e| WordNetCacheError::Corrupt(format!("{}.db: {}", cache, e)))?;
+        Ok(Lexicon::with_indexes(db, entries, synsets_by_ili, synset_ids, text_index, forms, frames, wikidata, new_ili, senses, invalid_ili))
     }
 
     /// Load a lexicon from a folder of YAML files
-    pub fn load<P: AsRef<Path>>(folder : P) -> Result<Lexicon, WordNetYAMLIOError> {
+    pub fn load<P: AsRef<Path>>(folder : P, cache : &str) -> Result<Lexicon, WordNetYAMLIOError> {
         let mut entries : HashMap<String, Entries> = HashMap::new();
         let mut synsets = HashMap::new();
         let mut synset_id_to_lexfile = HashMap::new();
         let mut sense_id_to_lemma_pos = HashMap::new();
-        let mut synsets_by_ili = HashMap::new();
+        let mut file_hashes = HashMap::new();
+        let mut frames = HashMap::new();
         let folder_files = fs::read_dir(folder)
             .map_err(|e| WordNetYAMLIOError::Io(format!("Could not list directory: {}", e)))?;
         println!("Loading WordNet");
@@ -69,10 +150,11 @@ impl Lexicon {
                 unwrap_or_else(|| "".to_string());
             if file_name.starts_with("entries-") && file_name.ends_with(".yaml") {
                 let key = file_name[8..9].to_string();
-                let entries2 : Entries =
-                    serde_yaml::from_reader(File::open(file.path())
-                        .map_err(|e| WordNetYAMLIOError::Io(format!("Error reading {} due to {}", file_name, e)))?)
-                        .map_err(|e| WordNetYAMLIOError::Serde(format!("Error reading {} due to {}", file_name, e)))?;
+                let data = fs::read(file.path())
+                    .map_err(|e| WordNetYAMLIOError::Io(format!("Error reading {} due to {}", file_name, e)))?;
+                file_hashes.insert(file_name.clone(), content_hash(&data));
+                let entries2 : Entries = serde_yaml::from_slice(&data)
+                    .map_err(|e| WordNetYAMLIOError::Serde(format!("Error reading {} due to {}", file_name, e)))?;
                 for (lemma, map) in entries2.0.iter() {
                     for (pos, entry) in map.iter() {
                         for sense in entry.sense.iter() {
@@ -92,15 +174,21 @@ impl Lexicon {
                 }).collect::<BTreeMap<_,_>>();
 
                 entries.insert(key, Entries(entries2));
-            } else if file_name.ends_with(".yaml") && file_name != "frames.yaml" {
-                let synsets2 : Synsets = serde_yaml::from_reader(
-                    File::open(file.path())
-                        .map_err(|e| WordNetYAMLIOError::Io(format!("Error reading {} due to {}", file_name, e)))?)
-                        .map_err(|e| WordNetYAMLIOError::Serde(format!("Error reading {} due to {}", file_name, e)))?;
+            } else if file_name == "frames.yaml" {
+                let data = fs::read(file.path())
+                    .map_err(|e| WordNetYAMLIOError::Io(format!("Error reading {} due to {}", file_name, e)))?;
+                file_hashes.insert(file_name.clone(), content_hash(&data));
+                frames = serde_yaml::from_slice(&data)
+                    .map_err(|e| WordNetYAMLIOError::Serde(format!("Error reading {} due to {}", file_name, e)))?;
+            } else if file_name.ends_with(".yaml") {
+                let data = fs::read(file.path())
+                    .map_err(|e| WordNetYAMLIOError::Io(format!("Error reading {} due to {}", file_name, e)))?;
+                file_hashes.insert(file_name.clone(), content_hash(&data));
+                let synsets2 : Synsets = serde_yaml::from_slice(&data)
+                    .map_err(|e| WordNetYAMLIOError::Serde(format!("Error reading {} due to {}", file_name, e)))?;
                 let lexname = file_name[0..file_name.len()-5].to_string();
-                for (id, value) in synsets2.0.iter() {
+                for id in synsets2.0.keys() {
                     synset_id_to_lexfile.insert(id.clone(), lexname.clone());
-                    synsets_by_ili.insert(value.ili.clone().unwrap_or_else(|| ILIID("".to_string())).0.clone(), id.clone());
                 }
                 let synsets2 = synsets2.0.into_iter().map(|(ssid, synset)| {
                     (ssid.clone(), Synset {
@@ -115,7 +203,35 @@ impl Lexicon {
         }
        bar.finish();
        add_reverse_links(&mut synsets, &entries, &synset_id_to_lexfile);
-       Ok(add_members(synsets, &mut entries))
+       add_members(synsets, &mut entries, file_hashes, frames, cache)
+    }
+
+    /// Load a lexicon from a WN-LMF XML file, which may be gzipped
+    pub fn load_lmf<P: AsRef<Path>>(file : P, cache : &str) -> Result<Lexicon, LmfError> {
+        let file = file.as_ref();
+        let file_name = file.file_name().and_then(|x| x.to_str())
+            .unwrap_or("").to_string();
+        let data = fs::read(file)
+            .map_err(|e| LmfError::Io(format!("Error reading {} due to {}", file_name, e)))?;
+        let LmfData { mut synsets, entries, frames } = if file_name.ends_with(".gz") {
+            read_lmf(BufReader::new(GzDecoder::new(data.as_slice())))?
+        } else {
+            read_lmf(data.as_slice())?
+        };
+        let mut synset_id_to_lexfile = HashMap::new();
+        for (lexname, synsets) in synsets.iter() {
+            for id in synsets.0.keys() {
+                synset_id_to_lexfile.insert(id.clone(), lexname.clone());
+            }
+        }
+        add_reverse_links(&mut synsets, &entries, &synset_id_to_lexfile);
+        // The cache can only be updated incrementally from the YAML files,
+        // so it is always rebuilt
+        std::fs::remove_file(format!("{}.db", cache)).ok();
+        let mut file_hashes = HashMap::new();
+        file_hashes.insert(file_name, content_hash(&data));
+        add_members(synsets, &entries, file_hashes, frames, cache)
+            .map_err(|e| LmfError::Io(e.to_string()))
     }
 
     ///// Get the lexicographer file name for a synset
@@ -123,7 +239,26 @@ impl Lexicon {
     //    self.synset_id_to_lexfile.get(synset_id).map(|x| x.clone())
     //}
 
-    /// Get the entry data for a lemma
+    /// All the lemmas in alphabetical order
+    pub fn lemmas(&self) -> Vec<&String> {
+        let mut lemmas : Vec<&String> = self.entries.keys().collect();
+        lemmas.sort();
+        lemmas
+    }
+
+    /// The text of a subcategorization frame
+    pub fn frame(&self, id : &str) -> Option<&str> {
+        self.frames.get(id).map(|f| f.as_str())
+    }
+
+    /// The IDs of all the subcategorization frames in alphabetical order
+    pub fn frame_ids(&self) -> Vec<&String> {
+        let mut ids : Vec<&String> = self.frames.keys().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Get the synsets of a lemma, most frequent sense first
     pub fn entry_by_lemma(&self, lemma : &str) -> Vec<SynsetId> {
         if let Some(e) = self.entries.get(lemma) {
             e.clone()
@@ -132,6 +267,118 @@ impl Lexicon {
         }
     }
 
+    /// Record how often each sense is tagged in the corpora, given the word
+    /// forms annotated with each synset, and order the synsets of each lemma
+    /// so that the most frequent sense comes first. The counts are kept in
+    /// the cache, which is only written if they have changed
+    pub fn set_sense_counts(&mut self, counts : &HashMap<SynsetId, HashMap<String, usize>>) -> Result<(), WordNetCacheError> {
+        let mut lemma_counts : HashMap<String, HashMap<SynsetId, u32>> = HashMap::new();
+        let mut changed = Vec::new();
+        {
+            let read_txn = self.db.begin_read().map_err(|e| WordNetCacheError::Corrupt(e.to_string()))?;
+            let table = read_txn.open_table(TABLE).map_err(|e| WordNetCacheError::Corrupt(e.to_string()))?;
+            let no_forms = HashMap::new();
+            // Every synset is checked, so that counts from corpora that are
+            // no longer used are cleared
+            for entry in table.iter().map_err(|e| WordNetCacheError::Corrupt(e.to_string()))? {
+                let (key, value) = entry.map_err(|e| WordNetCacheError::Corrupt(e.to_string()))?;
+                let ssid = SynsetId::new(key.value());
+                let forms = counts.get(&ssid).unwrap_or(&no_forms);
+                let mut synset = value.value();
+                let mut synset_changed = false;
+                let single = synset.members.len() == 1;
+                for member in synset.members.iter_mut() {
+                    let count = forms.iter()
+                        .filter(|(form, _)| single || self.tags_lemma(form, &member.lemma))
+                        .map(|(_, n)| *n as u32)
+                        .sum();
+                    if count != member.count {
+                        member.count = count;
+                        synset_changed = true;
+                    }
+                    if count > 0 {
+                        *lemma_counts.entry(member.lemma.clone()).or_insert_with(HashMap::new)
+                            .entry(ssid.clone()).or_insert(0) += count;
+                    }
+                }
+                if synset_changed {
+                    changed.push((ssid, synset));
+                }
+            }
+        }
+        if !changed.is_empty() {
+            let write_txn = self.db.begin_write().map_err(|e| WordNetCacheError::Corrupt(e.to_string()))?;
+            {
+                let mut table = write_txn.open_table(TABLE).map_err(|e| WordNetCacheError::Corrupt(e.to_string()))?;
+                for (ssid, synset) in changed {
+                    table.insert(ssid.0.as_str(), synset).map_err(|e| WordNetCacheError::Corrupt(e.to_string()))?;
+                }
+            }
+            write_txn.commit().map_err(|e| WordNetCacheError::Corrupt(e.to_string()))?;
+        }
+        for (lemma, synset_counts) in lemma_counts.iter() {
+            if let Some(ssids) = self.entries.get_mut(lemma) {
+                // A stable sort keeps the source order for untagged senses
+                ssids.sort_by_key(|s| std::cmp::Reverse(synset_counts.get(s).copied().unwrap_or(0)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check whether a word form tagged in a corpus is an occurrence of a lemma
+    fn tags_lemma(&self, form : &str, lemma : &str) -> bool {
+        let form = form.replace('_', " ");
+        form.eq_ignore_ascii_case(lemma) || self.lemmatize(&form).iter().any(|l| l == lemma)
+    }
+
+    /// Get the entry data for a word, which may be an inflected form if it
+    /// is not a lemma
+    pub fn entry_by_form(&self, form : &str) -> Vec<SynsetId> {
+        let mut synsets = Vec::new();
+        for lemma in self.lemmatize(form) {
+            for ssid in self.entry_by_lemma(&lemma) {
+                if !synsets.contains(&ssid) {
+                    synsets.push(ssid);
+                }
+            }
+        }
+        synsets
+    }
+
+    /// Find the lemmas of a word form: the form itself if it is a lemma,
+    /// then the lemmas listing it as an irregular form (so `saw` gives both
+    /// `saw` and `see`) and those found by removing inflectional endings
+    pub fn lemmatize(&self, form : &str) -> Vec<String> {
+        let lower = form.to_lowercase();
+        let mut lemmas = Vec::new();
+        for lemma in [form, lower.as_str()] {
+            if self.entries.contains_key(lemma) && !lemmas.iter().any(|l| l == lemma) {
+                lemmas.push(lemma.to_string());
+            }
+        }
+        for lemma in self.forms.get(form).into_iter().chain(self.forms.get(&lower)).flatten() {
+            if !lemmas.contains(lemma) {
+                lemmas.push(lemma.clone());
+            }
+        }
+        for (rules, pos) in [(NOUN_RULES, "n"), (VERB_RULES, "v"), (ADJ_RULES, "a"), (ADJ_RULES, "s")] {
+            for (suffix, ending) in rules.iter() {
+                if let Some(stem) = lower.strip_suffix(suffix) {
+                    if stem.is_empty() {
+                        continue;
+                    }
+                    let lemma = format!("{}{}", stem, ending);
+                    if !lemmas.contains(&lemma) && self.entries.get(&lemma)
+                        .map(|ssids| ssids.iter().any(|s| s.0.ends_with(&format!("-{}", pos))))
+                        .unwrap_or(false) {
+                        lemmas.push(lemma);
+                    }
+                }
+            }
+        }
+        lemmas
+    }
+
     /// Get synset data by ID
     pub fn synset_by_id(&self, synset_id : &SynsetId) -> Option<MemberSynset> {
         let read_txn = self.db.begin_read().unwrap();
@@ -139,6 +386,56 @@ impl Lexicon {
         table.get(synset_id.0.as_str()).unwrap().map(|x| x.value())
     }
 
+    /// Get the ID of the synset with an ILI
+    pub fn synset_id_by_ili(&self, ili : &str) -> Option<&SynsetId> {
+        self.synsets_by_ili.get(ili)
+    }
+
+    /// Get the ID of the synset of a sense, given its sense ID (which is its
+    /// sense key, such as `dog%1:05:00::`) or its WN-LMF identifier
+    pub fn synset_id_by_sense(&self, sense_id : &str) -> Option<&SynsetId> {
+        self.senses.get(sense_id).or_else(|| self.senses.get(&sense_key(sense_id)))
+    }
+
+    /// Set the table mapping the synset offsets of a Princeton WordNet
+    /// release to the synsets of this lexicon
+    pub fn set_pwn_mapping(&mut self, version : PwnVersion, mapping : HashMap<String, Vec<SynsetId>>) {
+        self.pwn_offsets.insert(version, mapping);
+    }
+
+    /// Get the IDs of the synsets for a Princeton WordNet synset offset
+    /// (such as `00001740-n`), several if the synset was split
+    pub fn synsets_by_pwn(&self, version : PwnVersion, offset : &str) -> Vec<SynsetId> {
+        offset_key(offset)
+            .and_then(|key| self.pwn_offsets.get(&version)?.get(&key))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The IDs of the synsets proposed for a new ILI (marked `in`)
+    pub fn synsets_with_new_ili(&self) -> &[SynsetId] {
+        &self.new_ili
+    }
+
+    /// The IDs of the synsets whose ILI is not a valid identifier
+    pub fn synsets_with_invalid_ili(&self) -> &[SynsetId] {
+        &self.invalid_ili
+    }
+
+    /// The ILIs of the lexicon with the ID of their synset, in order
+    pub fn ilis(&self) -> Vec<(&str, &SynsetId)> {
+        let mut ilis : Vec<(&str, &SynsetId)> = self.synsets_by_ili.iter()
+            .map(|(ili, id)| (ili.as_str(), id))
+            .collect();
+        ilis.sort();
+        ilis
+    }
+
+    /// Get the IDs of the synsets linked to a Wikidata item
+    pub fn synsets_by_wikidata(&self, qid : &str) -> Vec<SynsetId> {
+        self.wikidata.get(&qid.to_uppercase()).cloned().unwrap_or_default()
+    }
+
     /// Get synset by ILI
     pub fn synset_by_ili(&self, ili : &str) -> Option<(&SynsetId, MemberSynset)> {
         match self.synsets_by_ili.get(ili) {
@@ -151,24 +448,188 @@ impl Lexicon {
         }
     }
 
-    /// Get the lemmas that start with a string
-    pub fn lemma_by_prefix(&self, prefix: &str) -> Vec<String> {
-        let prefix = prefix.to_lowercase();
-        self.entries.iter().filter(|(k, _)| k.to_lowercase().starts_with(&prefix)).map(|(k, _)| k.clone()).collect()
+    /// Get the direct hypernyms and instance hypernyms of a synset
+    pub fn hypernyms(&self, synset_id : &SynsetId) -> Vec<SynsetId> {
+        self.synset_by_id(synset_id).map(|s| {
+            s.hypernym.into_iter().chain(s.instance_hypernym).collect()
+        }).unwrap_or_default()
+    }
+
+    /// Get the direct hyponyms and instance hyponyms of a synset
+    pub fn hyponyms(&self, synset_id : &SynsetId) -> Vec<SynsetId> {
+        self.synset_by_id(synset_id).map(|s| {
+            s.hyponym.into_iter().chain(s.instance_hyponym).collect()
+        }).unwrap_or_default()
+    }
+
+    /// Get every (instance) hypernym above a synset with its distance from
+    /// the synset, nearest first
+    pub fn ancestors(&self, synset_id : &SynsetId) -> Vec<(SynsetId, usize)> {
+        self.closure(synset_id, None, |s| self.hypernyms(s))
+    }
+
+    /// Get every (instance) hyponym below a synset with its distance from
+    /// the synset, nearest first, up to an optional depth
+    pub fn descendants(&self, synset_id : &SynsetId, depth : Option<usize>) -> Vec<(SynsetId, usize)> {
+        self.closure(synset_id, depth, |s| self.hyponyms(s))
+    }
+
+    /// Breadth-first walk of a relation
+    fn closure<F : Fn(&SynsetId) -> Vec<SynsetId>>(&self, synset_id : &SynsetId,
+        depth : Option<usize>, next : F) -> Vec<(SynsetId, usize)> {
+        let mut visited = HashSet::new();
+        visited.insert(synset_id.clone());
+        let mut result = Vec::new();
+        let mut frontier = vec![synset_id.clone()];
+        let mut d = 0;
+        while !frontier.is_empty() && depth.map(|x| d < x).unwrap_or(true) {
+            d += 1;
+            let mut next_frontier = Vec::new();
+            for s in frontier.iter() {
+                for t in next(s) {
+                    if visited.insert(t.clone()) {
+                        result.push((t.clone(), d));
+                        next_frontier.push(t);
+                    }
+                }
+            }
+            frontier = next_frontier;
+        }
+        result
+    }
+
+    /// Get every hypernym path from a root down to a synset
+    pub fn hypernym_paths(&self, synset_id : &SynsetId) -> Vec<Vec<SynsetId>> {
+        let mut paths = Vec::new();
+        let mut path = vec![synset_id.clone()];
+        self.hypernym_paths_from(&mut path, &mut paths);
+        paths
+    }
+
+    fn hypernym_paths_from(&self, path : &mut Vec<SynsetId>, paths : &mut Vec<Vec<SynsetId>>) {
+        let hypernyms = self.hypernyms(path.last().unwrap());
+        let mut extended = false;
+        for h in hypernyms {
+            // Guard against cycles in the source data
+            if path.contains(&h) {
+                continue;
+            }
+            extended = true;
+            path.push(h);
+            self.hypernym_paths_from(path, paths);
+            path.pop();
+        }
+        if !extended {
+            paths.push(path.iter().rev().cloned().collect());
+        }
+    }
+
+    /// Get up to `limit` lemmas that start with a string (ignoring case)
+    pub fn lemma_by_prefix(&self, prefix: &str, limit : usize) -> Vec<String> {
+        self.lemma_index.find(prefix, limit)
     }
 
-    /// Get the synsets that start with an ID
-    pub fn ssid_by_prefix(&self, prefix: &str) -> Vec<String> {
-        self.synset_ids.iter().filter(|k| k.0.starts_with(prefix)).map(|k| k.0.clone()).collect()
+    /// Get up to `limit` lemmas within `max_distance` edits of a query
+    /// (ignoring case), closest first and then those with most synsets
+    pub fn lemma_by_fuzzy(&self, query : &str, max_distance : usize, limit : usize) -> Vec<(String, usize)> {
+        let mut results = self.lemma_index.find_fuzzy(query, max_distance);
+        results.sort_by(|(a, d1), (b, d2)| {
+            d1.cmp(d2)
+                .then_with(|| self.entries.get(b).map(|x| x.len())
+                    .cmp(&self.entries.get(a).map(|x| x.len())))
+                .then_with(|| a.cmp(b))
+        });
+        results.truncate(limit);
+        results
     }
 
-    /// Get the ILIs that start with a string
-    pub fn ili_by_prefix(&self, prefix: &str) -> Vec<String> {
-        self.synsets_by_ili.iter().filter(|(k, _)| k.starts_with(prefix)).map(|(k, _)| k.clone()).collect()
+    /// Get up to `limit` synsets whose definitions or examples best match
+    /// a query
+    pub fn synset_by_text(&self, query : &str, limit : usize) -> Vec<(SynsetId, f64)> {
+        self.text_index.search(query, limit)
+    }
+
+    /// Get up to `limit` synsets that start with an ID
+    pub fn ssid_by_prefix(&self, prefix: &str, limit : usize) -> Vec<String> {
+        self.ssid_index.find(prefix, limit)
+    }
+
+    /// Get up to `limit` ILIs that start with a string
+    pub fn ili_by_prefix(&self, prefix: &str, limit : usize) -> Vec<String> {
+        self.ili_index.find(prefix, limit)
     }
 
 }
 
+/// A sorted list of case-folded keys, so that prefix lookups are a binary
+/// search followed by a scan of the matches only
+struct PrefixIndex(Vec<(String, String)>);
+
+impl PrefixIndex {
+    fn new<'a, I : Iterator<Item=&'a str>>(keys : I) -> PrefixIndex {
+        let mut index : Vec<(String, String)> = keys
+            .map(|k| (k.to_lowercase(), k.to_string()))
+            .collect();
+        // Keys that differ only in case are listed lower case first
+        index.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(&a.1)));
+        PrefixIndex(index)
+    }
+
+    fn find(&self, prefix : &str, limit : usize) -> Vec<String> {
+        let prefix = prefix.to_lowercase();
+        let start = self.0.partition_point(|(k, _)| k.as_str() < prefix.as_str());
+        self.0[start..].iter()
+            .take_while(|(k, _)| k.starts_with(&prefix))
+            .take(limit)
+            .map(|(_, k)| k.clone())
+            .collect()
+    }
+
+    /// Find all keys within `max_distance` edits of a query. As the keys are
+    /// sorted, the rows of the edit distance table are shared between keys
+    /// with a common prefix, and a prefix that is already too far from the
+    /// query skips every key that starts with it
+    fn find_fuzzy(&self, query : &str, max_distance : usize) -> Vec<(String, usize)> {
+        let query : Vec<char> = query.to_lowercase().chars().collect();
+        let mut rows : Vec<Vec<usize>> = vec![(0..=query.len()).collect()];
+        let mut prev : Vec<char> = Vec::new();
+        let mut results = Vec::new();
+        let mut i = 0;
+        while i < self.0.len() {
+            let key : Vec<char> = self.0[i].0.chars().collect();
+            let common = prev.iter().zip(key.iter()).take_while(|(a, b)| a == b).count();
+            rows.truncate(common + 1);
+            let mut pruned = None;
+            for (j, c) in key.iter().enumerate().skip(common) {
+                let last = &rows[j];
+                let mut row = vec![j + 1; query.len() + 1];
+                for q in 1..=query.len() {
+                    let cost = if query[q - 1] == *c { 0 } else { 1 };
+                    row[q] = (last[q] + 1).min(row[q - 1] + 1).min(last[q - 1] + cost);
+                }
+                let min = *row.iter().min().unwrap();
+                rows.push(row);
+                if min > max_distance {
+                    pruned = Some(j + 1);
+                    break;
+                }
+            }
+            prev = key;
+            if let Some(len) = pruned {
+                let prefix : String = prev[..len].iter().collect();
+                i += self.0[i..].partition_point(|(k, _)| k.starts_with(&prefix));
+            } else {
+                let distance = rows[rows.len() - 1][query.len()];
+                if distance <= max_distance {
+                    results.push((self.0[i].1.clone(), distance));
+                }
+                i += 1;
+            }
+        }
+        results
+    }
+}
+
 fn synset_by_id_mut<'a>(synsets : &'a mut HashMap<String, Synsets>, synset_id : &SynsetId,
     synset_id_to_lexfile : &HashMap<SynsetId, String>) -> Option<&'a mut Synset> {
     match synset_id_to_lexfile.get(synset_id) {
@@ -310,6 +771,47 @@ fn add_reverse_links(synsets : &mut HashMap<String, Synsets>, entries : &HashMap
             }
         }
     } 
+    // The sources give these on both senses, or they have no inverse
+    macro_rules! add_sense_sources {
+        ($rel:ident, $field:ident) => {
+            let mut elems = Vec::new();
+            for entries in entries.values() {
+                for (lemma, by_pos) in entries.0.iter() {
+                    for entry in by_pos.values() {
+                        for sense in entry.sense.iter() {
+                            for target in sense.$rel.iter() {
+                                if let Some((target_lemma, synset)) = sense_ids.get(target) {
+                                    elems.push((
+                                        sense.synset.clone(),
+                                        synset.clone(),
+                                        lemma.clone(),
+                                        target_lemma.clone(),
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            for (source_synset, target_synset, source_lemma, target_lemma) in elems {
+                if let Some(synset) = synset_by_id_mut(synsets, &source_synset, synset_id_to_lexfile) {
+                    synset.$field.push(SenseRelation {
+                        target_synset,
+                        source_lemma,
+                        target_lemma
+                    });
+                }
+            }
+        }
+    }
+    add_sense_sources!(also, also_sense);
+    add_sense_sources!(similar, similar_sense);
+    add_sense_sources!(domain_topic, domain_topic_sense);
+    add_sense_sources!(has_domain_topic, has_domain_topic_sense);
+    add_sense_sources!(domain_region, domain_region_sense);
+    add_sense_sources!(has_domain_region, has_domain_region_sense);
+    add_sense_sources!(other, other);
     add_sense_links!(agent, is_agent_of);
     add_sense_links!(material, is_material_of);
     add_sense_links!(event, is_event_of);
@@ -326,43 +828,132 @@ fn add_reverse_links(synsets : &mut HashMap<String, Synsets>, entries : &HashMap
     add_sense_links!(vehicle, is_vehicle_of);
 }
 
-pub fn add_members(synsets : HashMap<String, Synsets>, entries : &HashMap<String, Entries>) -> Lexicon {
+pub fn add_members(synsets : HashMap<String, Synsets>, entries : &HashMap<String, Entries>,
+    file_hashes : HashMap<String, u64>, frames : HashMap<String, String>, cache : &str) -> Result<Lexicon, WordNetYAMLIOError> {
     let mut synset_members = HashMap::new();
     let mut entry_map = HashMap::new();
     let mut ili = HashMap::new();
+    let mut wikidata = HashMap::new();
+    let mut new_ili = Vec::new();
+    let mut invalid_ili = Vec::new();
+    let mut senses = HashMap::new();
+    let mut synset_ids = Vec::new();
     for (_, synsets) in synsets {
         for (_, synset) in synsets.0 {
             let id = synset.id.clone().unwrap();
             for member in synset.members.iter() {
                 entry_map.entry(member.clone()).or_insert_with(Vec::new).push(id.clone());
             }
-            if let Some(ili_id) = synset.ili.as_ref() {
-                ili.insert(ili_id.0.clone(), id.clone());
+            match synset.ili.as_ref() {
+                Some(ili_id) if ili_id.is_new() => new_ili.push(id.clone()),
+                Some(ili_id) if !ili_id.is_valid() => invalid_ili.push(id.clone()),
+                Some(ili_id) => { ili.insert(ili_id.0.clone(), id.clone()); },
+                None => ()
+            }
+            if let Some(qid) = synset.wikidata.as_ref() {
+                wikidata.entry(qid.trim().to_uppercase()).or_insert_with(Vec::new).push(id.clone());
             }
             let m = synset_with_members(synset, entries);
+            synset_ids.push(id.clone());
             synset_members.insert(id, m);
         }
     }
-    std::fs::remove_file("wordnet.db").ok();
-    let db = Database::create("wordnet.db").unwrap();
-    let mut synset_ids = Vec::new();
-    let write_txn = db.begin_write().unwrap();
-    {
-        let mut table = write_txn.open_table(TABLE).unwrap();
-        for (id, synset) in synset_members {
-            synset_ids.push(id.clone());
-            table.insert(id.0.as_str(), synset).unwrap();
+    let db_file = format!("{}.db", cache);
+    let previous = if Path::new(&db_file).exists() {
+        read_cache(cache).ok().map(|(_, data)| data)
+    } else {
+        None
+    };
+    if previous.is_none() {
+        std::fs::remove_file(&db_file).ok();
+    }
+    let mut forms : HashMap<String, Vec<String>> = HashMap::new();
+    for entries in entries.values() {
+        for (lemma, by_pos) in entries.0.iter() {
+            for sense in by_pos.values().flat_map(|e| e.sense.iter()) {
+                senses.insert(sense.id.0.clone(), sense.synset.clone());
+            }
+            for form in by_pos.values().flat_map(|e| e.form.iter()) {
+                let lemmas = forms.entry(form.clone()).or_insert_with(Vec::new);
+                if !lemmas.contains(lemma) {
+                    lemmas.push(lemma.clone());
+                }
+            }
         }
     }
-    write_txn.commit().unwrap();
-    let mut data = File::create("wordnet.data").unwrap();
-    (&entry_map, &ili, &synset_ids).write_to_stream(&mut data).unwrap();
-    Lexicon {
-        db,            
-        entries: entry_map,
-        synsets_by_ili: ili,
-        synset_ids
+    let text_index = TextIndex::build(synset_members.values());
+    let search_file = format!("{}.search", cache);
+    let mut search_data = File::create(&search_file)
+        .map_err(|e| write_error(&search_file, e))?;
+    text_index.write_to_stream(&mut search_data)
+        .map_err(|e| write_error(&search_file, e))?;
+    let db = Database::create(&db_file)
+        .map_err(|e| write_error(&db_file, e))?;
+    let write_txn = db.begin_write()
+        .map_err(|e| write_error(&db_file, e))?;
+    {
+        let mut table = write_txn.open_table(TABLE)
+            .map_err(|e| write_error(&db_file, e))?;
+        if let Some((_, _, old_ids, old_hashes, _, _, _, _, _, _)) = previous {
+            let changed : HashSet<String> = file_hashes.iter()
+                .filter(|(f, h)| old_hashes.get(*f) != Some(*h))
+                .map(|(f, _)| f.clone())
+                .chain(old_hashes.keys().filter(|f| !file_hashes.contains_key(*f)).cloned())
+                .collect();
+            let removed : Vec<SynsetId> = old_ids.into_iter()
+                .filter(|id| !synset_members.contains_key(id))
+                .collect();
+            let mut dirty : HashSet<SynsetId> = synset_members.iter()
+                .filter(|(_, synset)| changed.contains(&format!("{}.yaml", synset.lexname)) ||
+                    synset.members.iter().any(|m| changed.contains(&format!("entries-{}.yaml", entry_key(&m.lemma)))))
+                .map(|(id, _)| id.clone())
+                .collect();
+            // Reverse links mean that the old and new neighbours of a
+            // changed synset may have changed too
+            let mut neighbours = Vec::new();
+            for id in dirty.iter().chain(removed.iter()) {
+                if let Some(synset) = synset_members.get(id) {
+                    neighbours.extend(synset.targets());
+                }
+                if let Some(old) = table.get(id.0.as_str()).map_err(|e| write_error(&db_file, e))? {
+                    neighbours.extend(old.value().targets());
+                }
+            }
+            dirty.extend(neighbours);
+            for id in removed.iter() {
+                table.remove(id.0.as_str())
+                    .map_err(|e| write_error(&db_file, e))?;
+            }
+            for id in dirty {
+                if let Some(synset) = synset_members.remove(&id) {
+                    table.insert(id.0.as_str(), synset)
+                        .map_err(|e| write_error(&db_file, e))?;
+                }
+            }
+        } else {
+            for (id, synset) in synset_members {
+                table.insert(id.0.as_str(), synset)
+                    .map_err(|e| write_error(&db_file, e))?;
+            }
+        }
     }
+    write_txn.commit()
+        .map_err(|e| write_error(&db_file, e))?;
+    let data_file = format!("{}.data", cache);
+    let mut data = File::create(&data_file)
+        .map_err(|e| write_error(&data_file, e))?;
+    (FORMAT_VERSION, source_fingerprint(&file_hashes)).write_to_stream(&mut data)
+        .map_err(|e| write_error(&data_file, e))?;
+    new_ili.sort();
+    invalid_ili.sort();
+    (&entry_map, &ili, &synset_ids, &file_hashes, &forms, &frames, &wikidata, &new_ili, &senses, &invalid_ili).write_to_stream(&mut data)
+        .map_err(|e| write_error(&data_file, e))?;
+    Ok(Lexicon::with_indexes(db, entry_map, ili, synset_ids, text_index, forms, frames, wikidata, new_ili, senses, invalid_ili))
+}
+
+/// An error writing one of the cache files
+fn write_error<E : std::fmt::Display>(file : &str, e : E) -> WordNetYAMLIOError {
+    WordNetYAMLIOError::Io(format!("Error writing {} due to {}", file, e))
 }
 
 pub fn synset_with_members(synset : Synset, entries : &HashMap<String, Entries>) -> MemberSynset {
@@ -382,7 +973,8 @@ pub fn synset_with_members(synset : Synset, entries : &HashMap<String, Entries>)
                     form: entry.form.clone(),
                     pronunciation: entry.pronunciation.clone(),
                     poskey: entry.poskey.clone(),
-                    entry_no: entry.poskey.as_ref().and_then(|x| x.entry_no())
+                    entry_no: entry.poskey.as_ref().and_then(|x| x.entry_no()),
+                    count: 0
                 });
             }
         }
@@ -428,6 +1020,13 @@ pub fn synset_with_members(synset : Synset, entries : &HashMap<String, Entries>)
         derivation: synset.derivation,
         exemplifies_sense: synset.exemplifies_sense,
         is_exemplified_by_sense: synset.is_exemplified_by_sense,
+        also_sense: synset.also_sense,
+        similar_sense: synset.similar_sense,
+        domain_topic_sense: synset.domain_topic_sense,
+        has_domain_topic_sense: synset.has_domain_topic_sense,
+        domain_region_sense: synset.domain_region_sense,
+        has_domain_region_sense: synset.has_domain_region_sense,
+        other: synset.other,
         agent: synset.agent,
         is_agent_of: synset.is_agent_of,
         material: synset.material,
@@ -459,7 +1058,72 @@ pub fn synset_with_members(synset : Synset, entries : &HashMap<String, Entries>)
     }
 }
 
-fn entry_key(lemma : &str) -> String {
+/// Read the format version, source fingerprint and indexes from `<cache>.data`
+fn read_cache(cache : &str) -> Result<(u64, LexiconData), WordNetCacheError> {
+    if !Path::new(&format!("{}.db", cache)).exists() {
+        return Err(WordNetCacheError::Missing(format!("{}.db", cache)));
+    }
+    let file = File::open(format!("{}.data", cache))
+        .map_err(|e| WordNetCacheError::Missing(format!("{}.data: {}", cache, e)))?;
+    let mut reader = BufReader::new(file);
+    let (version, fingerprint) = <(u32, u64)>::read_from_stream_unbuffered(&mut reader)
+        .map_err(|e| WordNetCacheError::Corrupt(format!("{}.data: {}", cache, e)))?;
+    if version != FORMAT_VERSION {
+        return Err(WordNetCacheError::Incompatible(version));
+    }
+    let data = LexiconData::read_from_stream_unbuffered(&mut reader)
+        .map_err(|e| WordNetCacheError::Corrupt(format!("{}.data: {}", cache, e)))?;
+    Ok((fingerprint, data))
+}
+
+/// The content hashes of the source files in a WordNet folder, or of a
+/// single WN-LMF file
+fn source_hashes(folder : &Path) -> Result<HashMap<String, u64>, WordNetYAMLIOError> {
+    let mut hashes = HashMap::new();
+    if folder.is_file() {
+        let file_name = folder.file_name().and_then(|x| x.to_str()).unwrap_or("").to_string();
+        let data = fs::read(folder)
+            .map_err(|e| WordNetYAMLIOError::Io(format!("Error reading {} due to {}", file_name, e)))?;
+        hashes.insert(file_name, content_hash(&data));
+        return Ok(hashes);
+    }
+    for file in fs::read_dir(folder)
+        .map_err(|e| WordNetYAMLIOError::Io(format!("Could not list directory: {}", e)))? {
+        let file = file.map_err(|e|
+            WordNetYAMLIOError::Io(format!("Could not list directory: {}", e)))?;
+        let file_name = file.file_name().to_string_lossy().to_string();
+        if file_name.ends_with(".yaml") {
+            let data = fs::read(file.path())
+                .map_err(|e| WordNetYAMLIOError::Io(format!("Error reading {} due to {}", file_name, e)))?;
+            hashes.insert(file_name, content_hash(&data));
+        }
+    }
+    Ok(hashes)
+}
+
+/// A single hash over all the source files of the lexicon
+fn source_fingerprint(hashes : &HashMap<String, u64>) -> u64 {
+    let mut files : Vec<_> = hashes.iter().collect();
+    files.sort();
+    let mut data = Vec::new();
+    for (name, hash) in files {
+        data.extend(name.as_bytes());
+        data.extend(hash.to_le_bytes());
+    }
+    content_hash(&data)
+}
+
+/// FNV-1a hash of the contents of a source file
+fn content_hash(data : &[u8]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for b in data {
+        hash ^= *b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+pub(crate) fn entry_key(lemma : &str) -> String {
     let key = lemma.to_lowercase().chars().next().expect("Empty lemma!");
     if key < 'a' || key > 'z' {
         '0'.to_string()
@@ -473,6 +1137,9 @@ fn entry_key(lemma : &str) -> String {
 pub struct PosKey(String);
 
 impl PosKey {
+    pub fn new(s : &str) -> PosKey { PosKey(s.to_string()) }
+    pub fn as_str(&self) -> &str { &self.0 }
+
     fn entry_no(&self) -> Option<u32> {
         if self.0.len() < 3 {
             None
@@ -501,6 +1168,7 @@ pub struct Entry {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub pronunciation : Vec<Pronunciation>,
     #[serde(default)]
+    #[serde(skip_serializing)]
     pub poskey : Option<PosKey>
 }
 
@@ -509,6 +1177,7 @@ pub struct Sense {
     pub id : SenseId,
     pub synset : SynsetId,
     #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub adjposition : Option<String>,
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
@@ -603,8 +1272,8 @@ pub struct Sense {
 
 #[derive(Debug, PartialEq, Serialize, Deserialize,Clone, Readable, Writable)]
 pub struct Pronunciation {
-    value : String,
-    variety : Option<String>
+    pub value : String,
+    pub variety : Option<String>
 }
 
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -615,9 +1284,11 @@ pub struct Synsets(pub BTreeMap<SynsetId, Synset>);
 pub struct Synset {
     // not found in serialized data
     #[serde(default)]
+    #[serde(skip_serializing)]
     pub id : Option<SynsetId>,
     // not found in serialized data
     #[serde(default)]
+    #[serde(skip_serializing)]
     pub lexname: Option<String>,
     pub definition : Vec<String>,
     #[serde(default)]
@@ -626,7 +1297,7 @@ pub struct Synset {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ili : Option<ILIID>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    wikidata : Option<String>,
+    pub wikidata : Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source : Option<String>,
     #[serde(default)]
@@ -726,6 +1397,21 @@ pub struct Synset {
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub is_exemplified_by_sense: Vec<SenseRelation>,
+    /// Sense relations that are only added to the synset of their source
+    #[serde(skip)]
+    pub also_sense: Vec<SenseRelation>,
+    #[serde(skip)]
+    pub similar_sense: Vec<SenseRelation>,
+    #[serde(skip)]
+    pub domain_topic_sense: Vec<SenseRelation>,
+    #[serde(skip)]
+    pub has_domain_topic_sense: Vec<SenseRelation>,
+    #[serde(skip)]
+    pub domain_region_sense: Vec<SenseRelation>,
+    #[serde(skip)]
+    pub has_domain_region_sense: Vec<SenseRelation>,
+    #[serde(skip)]
+    pub other: Vec<SenseRelation>,
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub agent: Vec<SenseRelation>,
@@ -825,7 +1511,7 @@ pub struct MemberSynset {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ili : Option<ILIID>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    wikidata : Option<String>,
+    pub wikidata : Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub source : Option<String>,
     #[serde(default)]
@@ -927,6 +1613,27 @@ pub struct MemberSynset {
     pub is_exemplified_by_sense: Vec<SenseRelation>,
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub also_sense: Vec<SenseRelation>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub similar_sense: Vec<SenseRelation>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub domain_topic_sense: Vec<SenseRelation>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub has_domain_topic_sense: Vec<SenseRelation>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub domain_region_sense: Vec<SenseRelation>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub has_domain_region_sense: Vec<SenseRelation>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub other: Vec<SenseRelation>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     pub agent: Vec<SenseRelation>,
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
@@ -1011,6 +1718,70 @@ pub struct MemberSynset {
     pub is_vehicle_of: Vec<SenseRelation>,
 }
 
+impl MemberSynset {
+    /// All synsets that this synset links to
+    pub fn targets(&self) -> Vec<SynsetId> {
+        let mut targets = Vec::new();
+        macro_rules! add_rel {
+            ($($rel:ident),*) => {
+                $(targets.extend(self.$rel.iter().cloned());)*
+            }
+        }
+        add_rel!(also, attribute, causes, domain_region, domain_topic, exemplifies,
+            entails, hypernym, instance_hypernym, mero_member, mero_part, mero_substance,
+            similar, hyponym, is_caused_by, has_domain_region, has_domain_topic,
+            is_exemplified_by, is_entailed_by, instance_hyponym, holo_member, holo_part,
+            holo_substance);
+        macro_rules! add_srel {
+            ($($rel:ident),*) => {
+                $(targets.extend(self.$rel.iter().map(|r| r.target_synset.clone()));)*
+            }
+        }
+        add_srel!(antonym, participle, is_participle_of, pertainym, is_pertainym_of,
+            derivation, exemplifies_sense, is_exemplified_by_sense, also_sense, similar_sense,
+            domain_topic_sense, has_domain_topic_sense, domain_region_sense,
+            has_domain_region_sense, other, agent, is_agent_of,
+            material, is_material_of, event, is_event_of, instrument, is_instrument_of,
+            location, is_location_of, by_means_of, is_by_means_of, undergoer,
+            is_undergoer_of, property, is_property_of, result, is_result_of, state,
+            is_state_of, uses, is_used_by, destination, is_destination_of, body_part,
+            is_body_part_of, vehicle, is_vehicle_of);
+        targets
+    }
+
+    /// The synset relations as stored in the sources, that is without the
+    /// inverse relations
+    pub fn relations(&self) -> Vec<(&'static str, &SynsetId)> {
+        let mut relations = Vec::new();
+        macro_rules! add_rel {
+            ($($rel:ident),*) => {
+                $(relations.extend(self.$rel.iter().map(|t| (stringify!($rel), t)));)*
+            }
+        }
+        add_rel!(also, attribute, causes, domain_region, domain_topic, exemplifies,
+            entails, hypernym, instance_hypernym, mero_member, mero_part, mero_substance,
+            similar);
+        relations
+    }
+
+    /// The sense relations as stored in the sources, that is without the
+    /// inverse relations
+    pub fn sense_relations(&self) -> Vec<(&'static str, &SenseRelation)> {
+        let mut relations = Vec::new();
+        macro_rules! add_srel {
+            ($($rel:ident),*) => {
+                $(relations.extend(self.$rel.iter().map(|r| (stringify!($rel), r)));)*
+            }
+        }
+        add_srel!(antonym, participle, pertainym, derivation, exemplifies_sense, also_sense,
+            similar_sense, domain_topic_sense, has_domain_topic_sense, domain_region_sense,
+            has_domain_region_sense, other, agent,
+            material, event, instrument, location, by_means_of, undergoer, property,
+            result, state, uses, destination, body_part, vehicle);
+        relations
+    }
+}
+
 impl redb::Value for MemberSynset {
     type SelfType<'a> = MemberSynset;
     type AsBytes<'a> = Vec<u8>;
@@ -1042,9 +1813,15 @@ pub struct Member {
     #[serde(default)]
     pub poskey : Option<PosKey>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub entry_no : Option<u32>
+    pub entry_no : Option<u32>,
+    /// The number of times the sense is tagged in the corpora
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_zero")]
+    pub count : u32
 }
 
+fn is_zero(n : &u32) -> bool { *n == 0 }
+
 #[derive(Debug, PartialEq, Serialize, Deserialize,Clone, Readable, Writable)]
 pub struct MemberSense {
     pub id : SenseId,
@@ -1128,12 +1905,35 @@ impl<'de> Visitor<'de> for ExampleVisitor
     }
 }
 
+/// An Interlingual Index identifier, either `i` followed by a number or `in`
+/// for a synset proposed for a new ILI. The sources are read as they are, so
+/// that one bad ILI does not stop the wordnet from loading, and
+/// `is_valid` tells if the identifier is well formed
 #[derive(Debug, PartialEq, Serialize, Deserialize,Clone, Readable, Writable)]
 pub struct ILIID(String);
 
 impl ILIID {
     #[allow(dead_code)]
     pub fn new(s : &str) -> ILIID { ILIID(s.to_string()) }
+    pub fn as_str(&self) -> &str { &self.0 }
+
+    /// Parse and validate an ILI identifier
+    pub fn parse(s : &str) -> Result<ILIID, String> {
+        let ili = ILIID(s.to_string());
+        if ili.is_valid() {
+            Ok(ili)
+        } else {
+            Err(format!("Bad ILI identifier {}", s))
+        }
+    }
+
+    /// Is this `in` or `i` followed by a number
+    pub fn is_valid(&self) -> bool {
+        self.0 == "in" || (self.0.len() > 1 && self.0.starts_with('i') && self.0[1..].chars().all(|c| c.is_ascii_digit()))
+    }
+
+    /// Is this synset proposed for a new ILI
+    pub fn is_new(&self) -> bool { self.0 == "in" }
 }
 
 #[allow(non_camel_case_types)]
@@ -1175,6 +1975,8 @@ impl PartOfSpeech {
 pub struct SenseId(String);
 
 impl SenseId {
+    pub fn new(s : &str) -> SenseId { SenseId(s.to_string()) }
+    pub fn as_str(&self) -> &str { &self.0 }
     pub fn to_string(&self) -> String {
         self.0.clone()
     }
@@ -1185,9 +1987,24 @@ pub struct SynsetId(String);
 
 impl SynsetId {
     pub fn new(s : &str) -> SynsetId { SynsetId(s.to_string()) }
+    pub fn as_str(&self) -> &str { &self.0 }
     pub fn to_string(&self) -> String { self.0.clone() }
 }
 
+#[derive(Error,Debug)]
+pub enum WordNetCacheError {
+    #[error("WordNet cache is missing: {0}")]
+    Missing(String),
+    #[error("WordNet cache was written by an incompatible build (format {0})")]
+    Incompatible(u32),
+    #[error("WordNet cache is out of date")]
+    Stale,
+    #[error("WordNet cache is corrupt: {0}")]
+    Corrupt(String),
+    #[error("Could not check WordNet sources: {0}")]
+    Source(String),
+}
+
 #[derive(Error,Debug)]
 pub enum WordNetYAMLIOError {
     #[error("Could not load WordNet: {0}")]
@@ -1196,3 +2013,147 @@ pub enum WordNetYAMLIOError {
     Serde(String),
 }
 
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A small lexicon: entity > animal > dog > puppy and animal > cat for
+    /// nouns, jog > run and eat for verbs. The puppy has no ILI.
+    pub(crate) const TAXONOMY : &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<LexicalResource xmlns:dc="https://globalwordnet.github.io/schemas/dc/">
+  <Lexicon id="oewn" label="Test" language="en" email="test@example.com" license="https://creativecommons.org/licenses/by/4.0/" version="test">
+    <LexicalEntry id="oewn-entity-n">
+      <Lemma writtenForm="entity" partOfSpeech="n"/>
+      <Sense id="oewn-entity__1.03.00.." dc:identifier="entity%1:03:00::" synset="oewn-00000001-n"/>
+    </LexicalEntry>
+    <LexicalEntry id="oewn-animal-n">
+      <Lemma writtenForm="animal" partOfSpeech="n"/>
+      <Sense id="oewn-animal__1.03.00.." dc:identifier="animal%1:03:00::" synset="oewn-00000002-n"/>
+    </LexicalEntry>
+    <LexicalEntry id="oewn-dog-n">
+      <Lemma writtenForm="dog" partOfSpeech="n">
+        <Pronunciation variety="GB">dɒɡ</Pronunciation>
+      </Lemma>
+      <Form writtenForm="dogs"/>
+      <Sense id="oewn-dog__1.05.00.." dc:identifier="dog%1:05:00::" synset="oewn-00000003-n"/>
+    </LexicalEntry>
+    <LexicalEntry id="oewn-cat-n">
+      <Lemma writtenForm="cat" partOfSpeech="n"/>
+      <Sense id="oewn-cat__1.05.00.." dc:identifier="cat%1:05:00::" synset="oewn-00000004-n"/>
+    </LexicalEntry>
+    <LexicalEntry id="oewn-puppy-n">
+      <Lemma writtenForm="puppy" partOfSpeech="n"/>
+      <Sense id="oewn-puppy__1.05.00.." dc:identifier="puppy%1:05:00::" synset="oewn-00000005-n"/>
+    </LexicalEntry>
+    <LexicalEntry id="oewn-run-v">
+      <Lemma writtenForm="run" partOfSpeech="v"/>
+      <Sense id="oewn-run__2.38.00.." dc:identifier="run%2:38:00::" synset="oewn-00000006-v" subcat="oewn-frame-via"/>
+    </LexicalEntry>
+    <LexicalEntry id="oewn-jog-v">
+      <Lemma writtenForm="jog" partOfSpeech="v"/>
+      <Sense id="oewn-jog__2.38.00.." dc:identifier="jog%2:38:00::" synset="oewn-00000007-v" subcat="oewn-frame-via"/>
+    </LexicalEntry>
+    <LexicalEntry id="oewn-eat-v">
+      <Lemma writtenForm="eat" partOfSpeech="v"/>
+      <Sense id="oewn-eat__2.34.00.." dc:identifier="eat%2:34:00::" synset="oewn-00000008-v"/>
+    </LexicalEntry>
+    <Synset id="oewn-00000001-n" ili="i35545" partOfSpeech="n" lexfile="noun.Tops">
+      <Definition>that which exists</Definition>
+    </Synset>
+    <Synset id="oewn-00000002-n" ili="i35563" partOfSpeech="n" lexfile="noun.Tops">
+      <Definition>a living organism</Definition>
+      <SynsetRelation relType="hypernym" target="oewn-00000001-n"/>
+    </Synset>
+    <Synset id="oewn-00000003-n" ili="i46360" partOfSpeech="n" lexfile="noun.animal">
+      <Definition>a "domestic" &lt;canine&gt; &amp; pet,
+in Welsh «ci»</Definition>
+      <Example dc:source="test">the dog barked
+twice</Example>
+      <SynsetRelation relType="hypernym" target="oewn-00000002-n"/>
+    </Synset>
+    <Synset id="oewn-00000004-n" ili="i46593" partOfSpeech="n" lexfile="noun.animal">
+      <Definition>a feline mammal</Definition>
+      <SynsetRelation relType="hypernym" target="oewn-00000002-n"/>
+    </Synset>
+    <Synset id="oewn-00000005-n" ili="" partOfSpeech="n" lexfile="noun.animal">
+      <Definition>a young dog</Definition>
+      <SynsetRelation relType="hypernym" target="oewn-00000003-n"/>
+    </Synset>
+    <Synset id="oewn-00000006-v" ili="i32084" partOfSpeech="v" lexfile="verb.motion">
+      <Definition>move fast on foot</Definition>
+    </Synset>
+    <Synset id="oewn-00000007-v" ili="i32090" partOfSpeech="v" lexfile="verb.motion">
+      <Definition>run slowly</Definition>
+      <SynsetRelation relType="hypernym" target="oewn-00000006-v"/>
+    </Synset>
+    <Synset id="oewn-00000008-v" ili="i34539" partOfSpeech="v" lexfile="verb.consumption">
+      <Definition>take in food</Definition>
+    </Synset>
+    <SyntacticBehaviour id="oewn-frame-via" subcategorizationFrame="Somebody ----s"/>
+  </Lexicon>
+</LexicalResource>
+"#;
+
+    /// Load a WN-LMF document into a lexicon cached in a temporary folder
+    pub(crate) fn lexicon_from_lmf(name : &str, xml : &str) -> Lexicon {
+        let dir = std::env::temp_dir().join(format!("oewn-test-{}-{}", std::process::id(), name));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("wn.xml");
+        fs::write(&file, xml).unwrap();
+        Lexicon::load_lmf(&file, dir.join("wn").to_str().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn load_taxonomy() {
+        let wn = lexicon_from_lmf("load", TAXONOMY);
+        let dog = wn.synset_by_id(&SynsetId::new("00000003-n")).unwrap();
+        assert_eq!(dog.members[0].lemma, "dog");
+        assert_eq!(dog.hypernym, vec![SynsetId::new("00000002-n")]);
+        assert_eq!(wn.hyponyms(&SynsetId::new("00000003-n")), vec![SynsetId::new("00000005-n")]);
+        assert_eq!(wn.synset_id_by_ili("i46360"), Some(&SynsetId::new("00000003-n")));
+        assert!(wn.synset_by_id(&SynsetId::new("00000005-n")).unwrap().ili.is_none());
+    }
+
+    #[test]
+    fn load_invalid_ili() {
+        let wn = lexicon_from_lmf("invalid-ili", &TAXONOMY.replace("\"i46593\"", "\"46593\""));
+        assert_eq!(wn.synsets_with_invalid_ili(), &[SynsetId::new("00000004-n")]);
+        assert_eq!(wn.synset_id_by_ili("46593"), None);
+        assert_eq!(wn.synset_by_id(&SynsetId::new("00000004-n")).unwrap().ili, Some(ILIID::new("46593")));
+    }
+
+    #[test]
+    fn lemmatize() {
+        // `saw` is both a lemma and an irregular form of `see`
+        let xml = TAXONOMY.replace(r#"    <Synset id="oewn-00000001-n""#, r#"    <LexicalEntry id="oewn-saw-n">
+      <Lemma writtenForm="saw" partOfSpeech="n"/>
+      <Sense id="oewn-saw__1.06.00.." synset="oewn-00000009-n"/>
+    </LexicalEntry>
+    <LexicalEntry id="oewn-see-v">
+      <Lemma writtenForm="see" partOfSpeech="v"/>
+      <Form writtenForm="saw"/>
+      <Form writtenForm="seen"/>
+      <Sense id="oewn-see__2.39.00.." synset="oewn-00000010-v"/>
+    </LexicalEntry>
+    <Synset id="oewn-00000009-n" ili="" partOfSpeech="n" lexfile="noun.artifact">
+      <Definition>a hand tool for cutting</Definition>
+    </Synset>
+    <Synset id="oewn-00000010-v" ili="" partOfSpeech="v" lexfile="verb.perception">
+      <Definition>perceive by sight</Definition>
+    </Synset>
+    <Synset id="oewn-00000001-n""#);
+        let wn = lexicon_from_lmf("lemmatize", &xml);
+        assert_eq!(wn.lemmatize("saw"), vec!["saw".to_string(), "see".to_string()]);
+        assert_eq!(wn.lemmatize("Saw"), vec!["saw".to_string(), "see".to_string()]);
+        assert_eq!(wn.lemmatize("seen"), vec!["see".to_string()]);
+        assert_eq!(wn.entry_by_form("saw"), vec![SynsetId::new("00000009-n"), SynsetId::new("00000010-v")]);
+        // Detachment rules only give lemmas of the right part of speech
+        assert_eq!(wn.lemmatize("cats"), vec!["cat".to_string()]);
+        assert_eq!(wn.lemmatize("puppies"), vec!["puppy".to_string()]);
+        assert_eq!(wn.lemmatize("jogs"), vec!["jog".to_string()]);
+        assert_eq!(wn.lemmatize("eating"), vec!["eat".to_string()]);
+        assert!(wn.lemmatize("dogged").is_empty());
+        assert!(wn.lemmatize("unknown").is_empty());
+    }
+}